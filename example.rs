@@ -8,6 +8,7 @@ use chrono::DateTime;
 use chrono_tz::Tz;
 use dotenv::dotenv;
 use rustygram::bot::Bot;
+use rustygram::notifier::{Notifier, OverflowPolicy};
 
 pub struct TradeSuccessNotification {
     pub listing_id: u32,
@@ -50,19 +51,27 @@ impl TradeFailureNotification {
     }
 }
 
-pub async fn send_success_notification(bot: &Bot, message: &TradeSuccessNotification) {
-    let message = message.craft_message();
-    let _ = bot.send_message(&message, None).await;
+// The helpers below only enqueue the message, so they never wait for Telegram on the
+// execution path. Call `notifier.flush().await` before shutting down.
+
+pub fn send_success_notification(notifier: &Notifier, message: &TradeSuccessNotification) {
+    let _ = notifier.notify(message.craft_message());
 }
 
-pub async fn send_failure_notification(bot: &Bot, message: &TradeFailureNotification) {
-    let message = message.craft_message();
-    let _ = bot.send_message(&message, None).await;
+pub fn send_failure_notification(notifier: &Notifier, message: &TradeFailureNotification) {
+    let _ = notifier.notify(message.craft_message());
 }
 
-pub async fn send_network_client_failure_notification(bot: &Bot, error: &str) {
+pub fn send_network_client_failure_notification(notifier: &Notifier, error: &str) {
     let message = format!("Network client failed with error: {}", error);
-    let _ = bot.send_message(&message, None).await;
+    let _ = notifier.notify(message);
+}
+
+/// Create a notifier sending messages in the background through the bot from `create_bot`.
+///
+/// If messages pile up faster than Telegram accepts them, the oldest ones are dropped.
+pub fn create_notifier() -> Notifier {
+    Notifier::new(create_bot(), 1024, OverflowPolicy::DropOldest)
 }
 
 /// Create a new bot using the Telegram bot token and chat ID from the environment variables.
@@ -133,7 +142,7 @@ mod tests {
     #[tokio::test]
     #[ignore]
     async fn test_send_success_notification() {
-        let bot = create_bot();
+        let notifier = create_notifier();
         let time = chrono::DateTime::parse_from_rfc3339("2020-12-31T23:59:59.999999999Z").unwrap();
         let singapore_time = time.with_timezone(&chrono_tz::Singapore);
         let notification = TradeSuccessNotification {
@@ -146,6 +155,7 @@ mod tests {
             timestamp: singapore_time,
         };
 
-        send_success_notification(&bot, &notification).await;
+        send_success_notification(&notifier, &notification);
+        notifier.flush().await;
     }
 }
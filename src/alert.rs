@@ -0,0 +1,526 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use tokio::sync::{watch, Notify};
+
+use crate::{
+    bot::Bot,
+    callbacks::{callback_action, Registration},
+    errors::Error,
+    requester::Requester,
+    types::{
+        CallbackQuery, InlineKeyboardButton, InlineKeyboardMarkup, Message, MessageId,
+        SendMessageOption, User,
+    },
+    utils,
+};
+
+/// How long the "Snooze 15m" button of an [`AckableAlert`] snoozes it for.
+pub const SNOOZE_DURATION: Duration = Duration::from_secs(15 * 60);
+
+const ACK_ACTION: &str = "ack";
+const SNOOZE_ACTION: &str = "snooze";
+const RESOLVE_ACTION: &str = "resolve";
+
+/// An alert with "Ack", "Snooze 15m" and "Resolve" buttons, which tells the
+/// caller whether a human has seen it.
+///
+/// When a button is pressed, the message is edited to show who pressed it
+/// and when, and the new [`AlertState`] is published to the caller. A snoozed
+/// alert is sent again once the snooze expires, unless it has been
+/// acknowledged or resolved in the meantime.
+///
+/// Callback queries must be passed to the bot, either by a [`Dispatcher`] or
+/// by [`Bot::route_callback_query`]. The buttons keep working after the
+/// `AckableAlert` is dropped, until the alert is resolved or cancelled with
+/// [`AckableAlert::cancel`].
+///
+/// [`Dispatcher`]: crate::dispatcher::Dispatcher
+#[derive(Debug, Clone)]
+pub struct AckableAlert {
+    state: watch::Receiver<AlertState>,
+    message_id: Arc<Mutex<MessageId>>,
+    cancelled: Arc<Notify>,
+}
+
+/// State of an [`AckableAlert`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AlertState {
+    /// Nobody has reacted to the alert yet, or its snooze has expired.
+    Open,
+
+    /// Somebody has seen the alert and is working on it.
+    Acknowledged { by: User, at: SystemTime },
+
+    /// The alert is sent again at `until`, unless acknowledged or resolved
+    /// before.
+    Snoozed { by: User, until: SystemTime },
+
+    /// The alert has been dealt with, its buttons are removed.
+    Resolved { by: User, at: SystemTime },
+}
+
+/// Handles button presses of an alert.
+struct Alert<B> {
+    bot: B,
+    registration: Registration,
+    state: watch::Sender<AlertState>,
+    message_id: Arc<Mutex<MessageId>>,
+    cancelled: Arc<Notify>,
+    text: String,
+    options: SendMessageOption,
+
+    /// The alert as shown in the chat, without status line.
+    message: Message,
+}
+
+impl AckableAlert {
+    /// Sends `text` as an alert to the chat of `bot`.
+    ///
+    /// The reply markup of `options` is replaced by the alert buttons.
+    pub async fn send(
+        bot: &Bot,
+        text: &str,
+        options: Option<SendMessageOption>,
+    ) -> Result<Self, Error> {
+        Self::start(bot, text, options).await
+    }
+
+    async fn start<B>(
+        bot: &B,
+        text: &str,
+        options: Option<SendMessageOption>,
+    ) -> Result<Self, Error>
+    where
+        B: Requester,
+    {
+        let registration = bot.callbacks().register();
+        let options = options.unwrap_or_default();
+
+        let message = bot
+            .send_message(text, Some(with_buttons(&options, &registration)))
+            .await?;
+
+        let (sender, state) = watch::channel(AlertState::Open);
+        let message_id = Arc::new(Mutex::new(message.id));
+        let cancelled = Arc::new(Notify::new());
+
+        let alert = Alert {
+            bot: bot.clone(),
+            registration,
+            state: sender,
+            message_id: Arc::clone(&message_id),
+            cancelled: Arc::clone(&cancelled),
+            text: text.to_owned(),
+            options,
+            message,
+        };
+        tokio::spawn(alert.run());
+
+        Ok(Self {
+            state,
+            message_id,
+            cancelled,
+        })
+    }
+
+    /// Stops handling the buttons of the alert, and removes them from the
+    /// message, e.g. once the condition raising the alert has cleared.
+    ///
+    /// The state stays as it is, and the waits for a state return it.
+    /// Cancelling a resolved alert has no effect.
+    pub fn cancel(&self) {
+        self.cancelled.notify_one();
+    }
+
+    /// Returns the current state of the alert.
+    #[must_use]
+    pub fn state(&self) -> AlertState {
+        self.state.borrow().clone()
+    }
+
+    /// Returns the id of the message showing the alert. Changes when a
+    /// snoozed alert is sent again.
+    #[must_use]
+    pub fn message_id(&self) -> MessageId {
+        *self
+            .message_id
+            .lock()
+            .expect("alert message id is poisoned")
+    }
+
+    /// Returns a channel receiving every change of the alert state.
+    #[must_use]
+    pub fn subscribe(&self) -> watch::Receiver<AlertState> {
+        self.state.clone()
+    }
+
+    /// Waits until the alert is acknowledged or resolved.
+    pub async fn acknowledged(&self) -> AlertState {
+        self.wait_for(|state| {
+            matches!(
+                state,
+                AlertState::Acknowledged { .. } | AlertState::Resolved { .. }
+            )
+        })
+        .await
+    }
+
+    /// Waits until the alert is resolved.
+    pub async fn resolved(&self) -> AlertState {
+        self.wait_for(|state| matches!(state, AlertState::Resolved { .. }))
+            .await
+    }
+
+    async fn wait_for<F>(&self, condition: F) -> AlertState
+    where
+        F: FnMut(&AlertState) -> bool,
+    {
+        let mut state = self.state.clone();
+        let result = state.wait_for(condition).await.map(|state| state.clone());
+
+        match result {
+            Ok(state) => state,
+            Err(_) => state.borrow().clone(),
+        }
+    }
+}
+
+impl AlertState {
+    /// Returns the line shown below the alert text, e.g.
+    /// `Acknowledged by @alice at 2024-01-02 03:04 UTC`.
+    #[must_use]
+    pub fn status_line(&self) -> Option<String> {
+        match self {
+            Self::Open => None,
+            Self::Acknowledged { by, at } => Some(format!(
+                "✅ Acknowledged by {} at {}",
+                utils::display_name(by),
+                utils::format_utc(*at)
+            )),
+            Self::Snoozed { by, until } => Some(format!(
+                "💤 Snoozed by {} until {}",
+                utils::display_name(by),
+                utils::format_utc(*until)
+            )),
+            Self::Resolved { by, at } => Some(format!(
+                "☑️ Resolved by {} at {}",
+                utils::display_name(by),
+                utils::format_utc(*at)
+            )),
+        }
+    }
+}
+
+impl<B> Alert<B>
+where
+    B: Requester,
+{
+    /// Handles button presses until the alert is resolved or cancelled.
+    ///
+    /// The callback route is removed once this returns.
+    async fn run(mut self) {
+        let cancelled = Arc::clone(&self.cancelled);
+
+        loop {
+            let snoozed_until = match &*self.state.borrow() {
+                AlertState::Snoozed { until, .. } => Some(*until),
+                _ => None,
+            };
+            let snooze_expired = async move {
+                match snoozed_until {
+                    Some(until) => {
+                        let remaining = until.duration_since(SystemTime::now()).unwrap_or_default();
+                        tokio::time::sleep(remaining).await;
+                    }
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                query = self.registration.recv() => match query {
+                    Some(query) if self.handle(&query).await => return,
+                    Some(_) => (),
+                    None => return,
+                },
+                () = snooze_expired => self.reopen().await,
+                () = cancelled.notified() => {
+                    let _ = self
+                        .bot
+                        .edit_message_reply_markup(self.message.id, None)
+                        .await;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Applies a button press, and returns whether the alert is resolved.
+    async fn handle(&mut self, query: &CallbackQuery) -> bool {
+        let current = self.state.borrow().clone();
+        let next = match transition(&current, callback_action(query), &query.from) {
+            Some(next) => next,
+            None => {
+                let status = current.status_line();
+                let _ = self
+                    .bot
+                    .answer_callback_query(&query.id, status.as_deref(), false)
+                    .await;
+                return false;
+            }
+        };
+
+        let status = next.status_line();
+        let _ = self
+            .bot
+            .answer_callback_query(&query.id, status.as_deref(), false)
+            .await;
+
+        // The state is published even if the message could not be edited.
+        let text = match &status {
+            Some(status) => format!("{}\n\n{status}", self.message_text()),
+            None => self.message_text().to_owned(),
+        };
+        let _ = self
+            .bot
+            .edit_message_entities(
+                self.message.id,
+                &text,
+                &self.message.entities,
+                keyboard(&next, &self.registration),
+            )
+            .await;
+
+        let resolved = matches!(next, AlertState::Resolved { .. });
+        self.state.send_replace(next);
+        resolved
+    }
+
+    /// Sends the alert again after its snooze expired.
+    async fn reopen(&mut self) {
+        let options = with_buttons(&self.options, &self.registration);
+
+        match self.bot.send_message(&self.text, Some(options)).await {
+            Ok(message) => {
+                let _ = self
+                    .bot
+                    .edit_message_reply_markup(self.message.id, None)
+                    .await;
+
+                *self
+                    .message_id
+                    .lock()
+                    .expect("alert message id is poisoned") = message.id;
+                self.message = message;
+            }
+            Err(_) => {
+                let _ = self
+                    .bot
+                    .edit_message_entities(
+                        self.message.id,
+                        self.message_text(),
+                        &self.message.entities,
+                        keyboard(&AlertState::Open, &self.registration),
+                    )
+                    .await;
+            }
+        }
+
+        self.state.send_replace(AlertState::Open);
+    }
+
+    fn message_text(&self) -> &str {
+        self.message.text.as_deref().unwrap_or(&self.text)
+    }
+}
+
+/// Returns the state after `action` was triggered by `by`, or `None` if the
+/// action is not possible in the `current` state.
+fn transition(current: &AlertState, action: Option<&str>, by: &User) -> Option<AlertState> {
+    let (by, now) = (by.clone(), SystemTime::now());
+
+    match (action?, current) {
+        (ACK_ACTION, AlertState::Open | AlertState::Snoozed { .. }) => {
+            Some(AlertState::Acknowledged { by, at: now })
+        }
+        (SNOOZE_ACTION, AlertState::Open) => Some(AlertState::Snoozed {
+            by,
+            until: now + SNOOZE_DURATION,
+        }),
+        (RESOLVE_ACTION, current) if !matches!(current, AlertState::Resolved { .. }) => {
+            Some(AlertState::Resolved { by, at: now })
+        }
+        _ => None,
+    }
+}
+
+/// Returns the buttons shown in the given state.
+fn keyboard(state: &AlertState, registration: &Registration) -> Option<InlineKeyboardMarkup> {
+    let ack = || InlineKeyboardButton::callback("Ack", registration.data(ACK_ACTION));
+    let snooze = || InlineKeyboardButton::callback("Snooze 15m", registration.data(SNOOZE_ACTION));
+    let resolve = || InlineKeyboardButton::callback("Resolve", registration.data(RESOLVE_ACTION));
+
+    let buttons = match state {
+        AlertState::Open => vec![ack(), snooze(), resolve()],
+        AlertState::Acknowledged { .. } => vec![resolve()],
+        AlertState::Snoozed { .. } => vec![ack(), resolve()],
+        AlertState::Resolved { .. } => return None,
+    };
+
+    Some(InlineKeyboardMarkup::new([buttons]))
+}
+
+fn with_buttons(options: &SendMessageOption, registration: &Registration) -> SendMessageOption {
+    SendMessageOption {
+        reply_markup: keyboard(&AlertState::Open, registration).map(Into::into),
+        ..options.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        alert::{
+            transition, AckableAlert, AlertState, ACK_ACTION, RESOLVE_ACTION, SNOOZE_ACTION,
+            SNOOZE_DURATION,
+        },
+        requester::mock::{user, MockBot, Request},
+        types::{InlineKeyboardMarkup, MessageId},
+    };
+
+    #[test]
+    fn buttons_move_alert_through_states() {
+        let by = user(7);
+
+        let snoozed = transition(&AlertState::Open, Some(SNOOZE_ACTION), &by).unwrap();
+        assert!(matches!(snoozed, AlertState::Snoozed { .. }));
+        assert!(transition(&snoozed, Some(SNOOZE_ACTION), &by).is_none());
+
+        let acknowledged = transition(&snoozed, Some(ACK_ACTION), &by).unwrap();
+        assert!(matches!(acknowledged, AlertState::Acknowledged { .. }));
+        assert!(transition(&acknowledged, Some(ACK_ACTION), &by).is_none());
+
+        let resolved = transition(&acknowledged, Some(RESOLVE_ACTION), &by).unwrap();
+        assert!(resolved
+            .status_line()
+            .unwrap()
+            .starts_with("☑️ Resolved by @alice at "));
+        assert!(transition(&resolved, Some(RESOLVE_ACTION), &by).is_none());
+        assert!(transition(&AlertState::Open, Some("unknown"), &by).is_none());
+    }
+
+    /// Returns the labels of the buttons of `markup`.
+    fn buttons(markup: Option<&InlineKeyboardMarkup>) -> Vec<&str> {
+        markup
+            .into_iter()
+            .flat_map(|markup| markup.inline_keyboard.iter().flatten())
+            .map(|button| button.text.as_str())
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ack_edits_message_and_answers_query() {
+        let bot = MockBot::new("-100");
+        let alert = AckableAlert::start(&bot, "Disk full", None).await.unwrap();
+        bot.take_requests();
+
+        assert!(bot.press("Ack", &user(7)));
+        let state = alert.acknowledged().await;
+        assert!(matches!(&state, AlertState::Acknowledged { by, .. } if *by == user(7)));
+        assert_eq!(alert.state(), state);
+
+        let status = state.status_line().unwrap();
+        assert!(status.starts_with("✅ Acknowledged by @alice at "));
+        match &bot.take_requests()[..] {
+            [Request::AnswerCallbackQuery { text, .. }, Request::EditMessageEntities {
+                message_id,
+                text: edited,
+                reply_markup,
+                ..
+            }] => {
+                assert_eq!(text.as_deref(), Some(status.as_str()));
+                assert_eq!(*message_id, MessageId(1));
+                assert_eq!(*edited, format!("Disk full\n\n{status}"));
+                assert_eq!(buttons(reply_markup.as_ref()), ["Resolve"]);
+            }
+            requests => panic!("unexpected requests: {requests:?}"),
+        }
+
+        assert!(bot.press("Resolve", &user(7)));
+        assert!(matches!(
+            alert.resolved().await,
+            AlertState::Resolved { .. }
+        ));
+        match &bot.take_requests()[..] {
+            [Request::AnswerCallbackQuery { .. }, Request::EditMessageEntities { reply_markup, .. }] =>
+            {
+                assert_eq!(*reply_markup, None);
+            }
+            requests => panic!("unexpected requests: {requests:?}"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expired_snooze_reopens_alert() {
+        let bot = MockBot::new("-100");
+        let alert = AckableAlert::start(&bot, "Disk full", None).await.unwrap();
+        let mut state = alert.subscribe();
+        bot.take_requests();
+
+        assert!(bot.press("Snooze 15m", &user(7)));
+        state.changed().await.unwrap();
+        assert!(matches!(*state.borrow(), AlertState::Snoozed { .. }));
+        match &bot.take_requests()[..] {
+            [Request::AnswerCallbackQuery { .. }, Request::EditMessageEntities { reply_markup, .. }] =>
+            {
+                assert_eq!(buttons(reply_markup.as_ref()), ["Ack", "Resolve"]);
+            }
+            requests => panic!("unexpected requests: {requests:?}"),
+        }
+
+        tokio::time::sleep(SNOOZE_DURATION - Duration::from_secs(60)).await;
+        assert_eq!(bot.take_requests(), []);
+
+        state.changed().await.unwrap();
+        assert_eq!(*state.borrow(), AlertState::Open);
+        assert_eq!(alert.message_id(), MessageId(2));
+        match &bot.take_requests()[..] {
+            [Request::SendMessage { text, .. }, Request::EditMessageReplyMarkup {
+                message_id,
+                reply_markup,
+                ..
+            }] => {
+                assert_eq!(text, "Disk full");
+                assert_eq!(*message_id, MessageId(1));
+                assert_eq!(*reply_markup, None);
+            }
+            requests => panic!("unexpected requests: {requests:?}"),
+        }
+        assert!(bot.press("Snooze 15m", &user(7)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancel_removes_buttons_and_route() {
+        let bot = MockBot::new("-100");
+        let alert = AckableAlert::start(&bot, "Disk full", None).await.unwrap();
+        let mut state = alert.subscribe();
+        bot.take_requests();
+
+        alert.cancel();
+        assert!(state.changed().await.is_err());
+        assert_eq!(alert.acknowledged().await, AlertState::Open);
+        assert_eq!(
+            bot.take_requests(),
+            [Request::EditMessageReplyMarkup {
+                chat_id: "-100".to_owned(),
+                message_id: MessageId(1),
+                reply_markup: None,
+            }]
+        );
+        assert!(!bot.press("Ack", &user(7)));
+    }
+}
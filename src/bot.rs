@@ -1,5 +1,3 @@
-use reqwest::header::HeaderMap;
-use reqwest::header::HeaderValue;
 use reqwest::multipart;
 use reqwest::Client;
 use std::fs;
@@ -7,14 +5,54 @@ use std::io::Read;
 use std::{sync::Arc, time::Duration};
 
 use crate::{
-    errors::{ErrorResult, TelegramErrorResult},
-    types::{RequestObj, SendMessageOption, StatusCode},
+    broadcast::{self, BroadcastReport, Pacing, DEFAULT_BROADCAST_CONCURRENCY},
+    callbacks::Callbacks,
+    confirmation::{self, Decision},
+    errors::{Error, TelegramErrorResult},
+    long_text::{self, LongText, CAPTION_LENGTH_LIMIT, MESSAGE_LENGTH_LIMIT},
+    migration::Migrations,
+    retry::RetryPolicy,
+    rich_text::RichText,
+    throttle::{Limits, RateLimiter},
+    types::{
+        BotCommand, CallbackQuery, ChatId, ForumTopic, InlineKeyboardMarkup, Message,
+        MessageEntity, MessageId, MessageText, RequestObj, ResponseObj, SendMessageOption, Update,
+        User, WebhookInfo,
+    },
     utils,
 };
 
 pub const TELEGRAM_API_URL: &str = "https://api.telegram.org";
 const SEND_MESSAGE_METHOD: &str = "sendMessage";
 const SEND_MEDIA_METHOD: &str = "sendMediaGroup";
+const EDIT_MESSAGE_TEXT_METHOD: &str = "editMessageText";
+const EDIT_MESSAGE_CAPTION_METHOD: &str = "editMessageCaption";
+const EDIT_MESSAGE_REPLY_MARKUP_METHOD: &str = "editMessageReplyMarkup";
+const DELETE_MESSAGE_METHOD: &str = "deleteMessage";
+const DELETE_MESSAGES_METHOD: &str = "deleteMessages";
+const PIN_CHAT_MESSAGE_METHOD: &str = "pinChatMessage";
+const UNPIN_CHAT_MESSAGE_METHOD: &str = "unpinChatMessage";
+const GET_UPDATES_METHOD: &str = "getUpdates";
+const SET_WEBHOOK_METHOD: &str = "setWebhook";
+const DELETE_WEBHOOK_METHOD: &str = "deleteWebhook";
+const GET_WEBHOOK_INFO_METHOD: &str = "getWebhookInfo";
+const GET_ME_METHOD: &str = "getMe";
+const SET_MY_COMMANDS_METHOD: &str = "setMyCommands";
+const ANSWER_CALLBACK_QUERY_METHOD: &str = "answerCallbackQuery";
+const CREATE_FORUM_TOPIC_METHOD: &str = "createForumTopic";
+const EDIT_FORUM_TOPIC_METHOD: &str = "editForumTopic";
+const CLOSE_FORUM_TOPIC_METHOD: &str = "closeForumTopic";
+const REOPEN_FORUM_TOPIC_METHOD: &str = "reopenForumTopic";
+
+/// Maximum number of messages `deleteMessages` accepts in one call.
+const DELETE_MESSAGES_LIMIT: usize = 100;
+
+/// Time given to Telegram on top of the long polling timeout to answer
+/// `getUpdates`.
+const GET_UPDATES_TIMEOUT_MARGIN: Duration = Duration::from_secs(5);
+
+/// Maximum number of times a request is repeated after hitting flood control.
+pub(crate) const MAX_FLOOD_WAIT_RETRIES: u32 = 5;
 
 /// A requests sender.
 ///
@@ -26,6 +64,13 @@ const SEND_MEDIA_METHOD: &str = "sendMediaGroup";
 /// `Bot::clone` is relatively cheap, so if you need to share `Bot`, it's
 /// recommended to clone it, instead of wrapping it in [`Arc<_>`].
 ///
+/// ## Shared state
+///
+/// Clones share the rate limiter, the routes of callback queries, the chats
+/// upgraded to supergroups and the hook set with
+/// [`Bot::with_migration_hook`]. Setting the hook on one clone sets it on all
+/// of them, including the clones made before.
+///
 /// [`Arc`]: std::sync::Arc
 /// [Telegram Bot API]: https://core.telegram.org/bots/api
 #[must_use]
@@ -35,6 +80,13 @@ pub struct Bot {
     pub chat_id: Arc<str>,
     pub api_url: Arc<reqwest::Url>,
     pub client: Client,
+    flood_wait: bool,
+    retry_policy: Arc<RetryPolicy>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    callbacks: Arc<Callbacks>,
+    approvers: Arc<[u64]>,
+    broadcast_concurrency: usize,
+    migrations: Arc<Migrations>,
 }
 
 /// Constructors
@@ -82,62 +134,298 @@ impl Bot {
             chat_id,
             api_url,
             client,
+            flood_wait: false,
+            retry_policy: Arc::new(RetryPolicy::none()),
+            rate_limiter: None,
+            callbacks: Arc::new(Callbacks::default()),
+            approvers: Arc::new([]),
+            broadcast_concurrency: DEFAULT_BROADCAST_CONCURRENCY,
+            migrations: Arc::new(Migrations::default()),
+        }
+    }
+
+    /// Returns a clone of this `Bot` which sends to `chat_id` instead of its
+    /// default chat.
+    ///
+    /// The clone shares the http-client, the rate limiter and all other
+    /// settings, so that a single `Bot` can serve many chats:
+    ///
+    /// ```no_run
+    /// # async fn run(bot: rustygram::bot::Bot) -> Result<(), rustygram::Error> {
+    /// bot.for_chat(-1001234567890).send_message("Filled 0.1 BTC", None).await?;
+    /// bot.for_chat("@mychannel").send_message("Daily PnL: +2.4%", None).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn for_chat<C>(&self, chat_id: C) -> Self
+    where
+        C: Into<ChatId>,
+    {
+        Self {
+            chat_id: chat_id.into().to_string().into(),
+            ..self.clone()
         }
     }
 }
 
+/// Settings
+impl Bot {
+    /// Enables or disables waiting out Telegram's flood control.
+    ///
+    /// When enabled, a request rejected with `retry_after` (HTTP 429) is
+    /// repeated after sleeping for the requested interval, instead of
+    /// returning [`Error::Api`] to the caller. Disabled by default.
+    ///
+    /// [`Error::Api`]: crate::errors::Error::Api
+    pub fn with_flood_wait(mut self, enabled: bool) -> Self {
+        self.flood_wait = enabled;
+        self
+    }
+
+    /// Sets the policy used to repeat failed requests.
+    ///
+    /// By default requests are not repeated, see [`RetryPolicy::none`].
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Arc::new(policy);
+        self
+    }
+
+    /// Paces messages so that Telegram's rate `limits` are never exceeded.
+    ///
+    /// Calls wait for a free slot instead of being rejected with HTTP 429.
+    /// The limiter is shared by all clones of this `Bot`. Disabled by default.
+    pub fn with_rate_limits(mut self, limits: Limits) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(limits)));
+        self
+    }
+
+    /// Sets how many messages [`Bot::broadcast`] sends at the same time.
+    ///
+    /// Defaults to [`DEFAULT_BROADCAST_CONCURRENCY`].
+    ///
+    /// # Panics
+    /// If `limit` is `0`.
+    pub fn with_broadcast_concurrency(mut self, limit: usize) -> Self {
+        assert!(limit > 0, "broadcast concurrency must be positive");
+        self.broadcast_concurrency = limit;
+        self
+    }
+
+    /// Sets a function called when a chat has been upgraded to a supergroup,
+    /// with the old chat and the id of the supergroup, e.g. to store the new
+    /// id in the configuration of the application.
+    ///
+    /// Unlike the other settings, the hook is not specific to the returned
+    /// `Bot`: it is set on every clone sharing its state, also the ones made
+    /// before this call, e.g. with [`Bot::for_chat`], and replaces the hook any
+    /// of them set earlier. See [Shared state](Bot#shared-state).
+    ///
+    /// New messages to
+    /// an upgraded chat are always sent to the supergroup, whether a hook is
+    /// set or not. Edits, deletions and pins of messages sent before the
+    /// upgrade still target the old chat, and fail with the error telling the
+    /// new id.
+    pub fn with_migration_hook<F>(self, hook: F) -> Self
+    where
+        F: Fn(&ChatId, i64) + Send + Sync + 'static,
+    {
+        self.migrations.set_hook(Arc::new(hook));
+        self
+    }
+
+    /// Sets the ids of the users allowed to answer [`Bot::ask_confirmation`].
+    pub fn with_approvers<I>(mut self, user_ids: I) -> Self
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        self.approvers = user_ids.into_iter().collect();
+        self
+    }
+}
+
 /// Core Functionality
 impl Bot {
     /// Sends a request to the Telegram Bot API asynchronously
-    pub async fn send_message(
+    ///
+    /// `msg` is either a string, formatted with the parse mode of `options`,
+    /// or a [`RichText`] which is sent with its entities.
+    ///
+    /// Returns the sent [`Message`], the first one if the text was split as
+    /// requested by the [`LongText`] option, see [`Bot::send_message_parts`].
+    ///
+    /// [`RichText`]: crate::rich_text::RichText
+    pub async fn send_message<T>(
         &self,
-        msg: &str,
+        msg: T,
         options: Option<SendMessageOption>,
-    ) -> Result<(), ErrorResult> {
-        let request_json_obj = self.build_request_obj(msg, options);
-
-        let response = self
-            .client
-            .post(method_url(
-                self.api_url(),
-                self.token(),
-                SEND_MESSAGE_METHOD,
-            ))
-            .json(&request_json_obj)
-            .send()
-            .await;
+    ) -> Result<Message, Error>
+    where
+        T: Into<MessageText>,
+    {
+        let messages = self.send_message_parts(msg, options).await?;
 
-        match response {
-            Ok(res) => {
-                if res.status().is_success() {
-                    Ok(())
-                } else {
-                    match res.json::<TelegramErrorResult>().await {
-                        Ok(err_result) => {
-                            return utils::create_error_result_str(
-                                StatusCode::ErrorInternalError,
-                                &err_result.description.to_owned(),
-                            )
-                        }
-                        Err(_) => {
-                            return utils::create_error_result_str(
-                                StatusCode::ErrorInternalError,
-                                "Error converting telegram error response to json",
+        // `send_message_parts` fails rather than sending no part at all.
+        let first = messages
+            .into_iter()
+            .next()
+            .expect("at least one part of the message is sent");
+        Ok(first)
+    }
+
+    /// Sends a message like [`Bot::send_message`], split into several
+    /// messages if it is too long and the [`LongText`] option asks for it.
+    ///
+    /// The reply markup is attached to the last message only. Returns all
+    /// sent messages, in order. If sending a part fails, the remaining parts
+    /// are not sent.
+    pub async fn send_message_parts<T>(
+        &self,
+        msg: T,
+        options: Option<SendMessageOption>,
+    ) -> Result<Vec<Message>, Error>
+    where
+        T: Into<MessageText>,
+    {
+        let msg = msg.into();
+        if msg.is_empty() {
+            return Err(Error::InvalidInput(
+                "message text must not be empty".to_owned(),
+            ));
+        }
+
+        let parse_mode = options.as_ref().and_then(|option| option.parse_mode);
+        let parts = match options.as_ref().map(|option| &option.long_text) {
+            Some(LongText::Split { numbered }) => {
+                long_text::split(&msg, parse_mode, MESSAGE_LENGTH_LIMIT, *numbered)?
+            }
+            Some(LongText::Document(document)) => {
+                match long_text::text_document(&msg, parse_mode, document)? {
+                    Some((preview, contents)) => {
+                        let document = self
+                            .send_document(
+                                contents.into_bytes(),
+                                &document.file_name,
+                                "text/plain",
+                                preview,
+                                options.clone(),
                             )
-                        }
+                            .await?;
+                        return Ok(vec![document]);
                     }
+                    None => vec![msg],
                 }
             }
-            Err(e) => {
-                return utils::create_error_result_str(
-                    StatusCode::ErrorInternalError,
-                    &format!("Error sending HTTP request; err={}", e),
-                )
-            }
+            Some(LongText::Reject) | None => vec![msg],
+        };
+
+        self.send_parts(&parts, options).await
+    }
+
+    /// Sends `msg` to every chat of `chats`, and reports the outcome per
+    /// chat.
+    ///
+    /// Up to [`Bot::with_broadcast_concurrency`] messages are sent at the
+    /// same time, paced by the rate limits if set with
+    /// [`Bot::with_rate_limits`], or else by Telegram's default limit of
+    /// messages per second across all chats. Flood control is waited out per
+    /// chat, even if not enabled with [`Bot::with_flood_wait`]. A failed
+    /// chat does not stop the broadcast.
+    ///
+    /// Chats upgraded to a supergroup before are sent to the supergroup and
+    /// reported as delivered, while an upgrade found out by the broadcast is
+    /// reported as [`DeliveryStatus::Migrated`].
+    ///
+    /// [`DeliveryStatus::Migrated`]: crate::broadcast::DeliveryStatus::Migrated
+    pub async fn broadcast<I, C>(
+        &self,
+        chats: I,
+        msg: &str,
+        options: Option<SendMessageOption>,
+    ) -> BroadcastReport
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<ChatId>,
+    {
+        let pacing = Pacing {
+            limiter: self.rate_limiter.is_none().then(|| {
+                RateLimiter::new(Limits {
+                    messages_per_sec_chat: 0,
+                    messages_per_min_group: 0,
+                    ..Limits::default()
+                })
+            }),
+            flood_wait: !self.flood_wait,
+        };
+
+        let chats = chats.into_iter().map(Into::into).collect();
+        broadcast::send_all(
+            self,
+            chats,
+            msg,
+            options,
+            self.broadcast_concurrency,
+            &pacing,
+        )
+        .await
+    }
+
+    /// Sends a prepared `sendMessage` request.
+    pub(crate) async fn send_request_obj(
+        &self,
+        request_obj: &RequestObj,
+    ) -> Result<Message, Error> {
+        self.send_request(
+            SEND_MESSAGE_METHOD,
+            Some(request_obj.chat_id()),
+            request_obj,
+        )
+        .await
+    }
+
+    /// Sends the parts of a message in order, with the reply markup attached
+    /// to the last part only.
+    async fn send_parts(
+        &self,
+        parts: &[MessageText],
+        options: Option<SendMessageOption>,
+    ) -> Result<Vec<Message>, Error> {
+        let count = parts.len();
+        let mut messages = Vec::with_capacity(count);
+        for (index, part) in parts.iter().enumerate() {
+            let options = if index + 1 == count {
+                options.clone()
+            } else {
+                options.clone().map(|option| SendMessageOption {
+                    reply_markup: None,
+                    ..option
+                })
+            };
+
+            let request_json_obj = self.build_request_obj(part, options);
+            messages.push(self.send_request_obj(&request_json_obj).await?);
         }
+
+        Ok(messages)
     }
 
-    pub async fn send_csv(&self, filepath: &str, caption: &str) -> Result<(), ErrorResult> {
+    /// Sends a CSV file as a document with the given caption.
+    ///
+    /// Returns the sent [`Message`].
+    pub async fn send_csv(&self, filepath: &str, caption: &str) -> Result<Message, Error> {
+        self.send_csv_with_options(filepath, caption, None).await
+    }
+
+    /// Sends a CSV file as a document with the given caption, formatted with
+    /// the parse mode of `options`, and sent silently or to a forum topic if
+    /// requested. The reply markup of `options` is not supported and ignored.
+    ///
+    /// Returns the sent [`Message`].
+    pub async fn send_csv_with_options(
+        &self,
+        filepath: &str,
+        caption: &str,
+        options: Option<SendMessageOption>,
+    ) -> Result<Message, Error> {
         let mut file = fs::File::open(filepath)?;
         let mut contents = Vec::new();
         file.read_to_end(&mut contents)?;
@@ -147,57 +435,641 @@ impl Bot {
             .and_then(|n| n.to_str())
             .unwrap_or("file.csv");
 
-        let part = multipart::Part::bytes(contents)
-            .file_name(file_name.to_string())
-            .mime_str("text/csv")?;
+        self.send_document(contents, file_name, "text/csv", caption.into(), options)
+            .await
+    }
 
-        let media = serde_json::json!([
-            {
-                "type": "document",
-                "media": "attach://file",
-                "caption": caption
+    /// Sends `contents` as a document named `file_name` with the given
+    /// caption.
+    pub(crate) async fn send_document(
+        &self,
+        contents: Vec<u8>,
+        file_name: &str,
+        mime: &str,
+        caption: MessageText,
+        options: Option<SendMessageOption>,
+    ) -> Result<Message, Error> {
+        // A caption which is too long is continued in messages after the
+        // document, if splitting is enabled.
+        let (caption, rest) = match options.as_ref().map(|option| &option.long_text) {
+            Some(LongText::Split { .. }) => {
+                let parse_mode = options.as_ref().and_then(|option| option.parse_mode);
+                long_text::split_first(&caption, parse_mode, CAPTION_LENGTH_LIMIT)?
             }
-        ]);
-
-        let form = multipart::Form::new()
-            .text("chat_id", self.chat_id.to_string())
-            .text("media", media.to_string())
-            .part("file", part);
-
-        let response = self
-            .client
-            .post(method_url(self.api_url(), self.token(), "sendMediaGroup"))
-            .header(reqwest::header::CONTENT_TYPE, "multipart/form-data")
-            .multipart(form)
-            .send()
-            .await?;
+            Some(LongText::Reject | LongText::Document(_)) | None => (caption, RichText::new()),
+        };
+        let parse_mode = match caption.entities() {
+            Some(_) => None,
+            None => parse_mode_str(options.as_ref()),
+        };
+
+        let media = serde_json::json!([json_payload(&serde_json::json!({
+            "type": "document",
+            "media": "attach://file",
+            "caption": caption.text(),
+            "parse_mode": parse_mode,
+            "caption_entities": caption.entities(),
+        }))?]);
+        let disable_notification = options
+            .as_ref()
+            .is_some_and(|option| option.disable_notification);
+        let message_thread_id = options.as_ref().and_then(|option| option.message_thread_id);
 
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            let err_result =
-                response
-                    .json::<TelegramErrorResult>()
+        let mut chat_id = self.migrations.resolve(&self.chat_id);
+        let mut migrated = false;
+        let messages: Vec<Message> = loop {
+            let result = self
+                .execute(SEND_MEDIA_METHOD, Some(&chat_id), |request| {
+                    // A multipart form can be sent only once, so it is rebuilt
+                    // for every attempt.
+                    let part = multipart::Part::bytes(contents.clone())
+                        .file_name(file_name.to_owned())
+                        .mime_str(mime)
+                        .map_err(|_| Error::InvalidInput(format!("invalid mime type: {mime}")))?;
+
+                    let mut form = multipart::Form::new()
+                        .text("chat_id", chat_id.clone())
+                        .text("media", media.to_string())
+                        .part("file", part);
+                    if disable_notification {
+                        form = form.text("disable_notification", "true");
+                    }
+                    if let Some(message_thread_id) = message_thread_id {
+                        form = form.text("message_thread_id", message_thread_id.to_string());
+                    }
+
+                    Ok(request
+                        .header(reqwest::header::CONTENT_TYPE, "multipart/form-data")
+                        .multipart(form))
+                })
+                .await;
+
+            match result {
+                Err(err) if !migrated => match self.migrations.follow(&chat_id, &err) {
+                    Some(to) => {
+                        chat_id = to;
+                        migrated = true;
+                    }
+                    None => return Err(err),
+                },
+                result => break result?,
+            }
+        };
+
+        let message = messages.into_iter().next().ok_or_else(|| {
+            Error::Json(serde::de::Error::custom(
+                "sendMediaGroup returned no messages",
+            ))
+        })?;
+
+        if !rest.is_empty() {
+            let numbered = matches!(
+                options.as_ref().map(|option| &option.long_text),
+                Some(LongText::Split { numbered: true })
+            );
+            let parts = long_text::split(&rest.into(), None, MESSAGE_LENGTH_LIMIT, numbered)?;
+            self.send_parts(&parts, options).await?;
+        }
+
+        Ok(message)
+    }
+
+    /// Calls `method` of the Telegram Bot API with a JSON `payload`.
+    async fn request<P, R>(
+        &self,
+        method: &str,
+        chat_id: Option<&str>,
+        payload: &P,
+    ) -> Result<R, Error>
+    where
+        P: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let payload = json_payload(payload)?;
+        self.execute(method, chat_id, |request| Ok(request.json(&payload)))
+            .await
+    }
+
+    /// Calls `method` of the Telegram Bot API with a JSON `payload` which
+    /// sends a new message.
+    ///
+    /// If the chat of the payload has been upgraded to a supergroup, the
+    /// message is sent to the supergroup instead. Requests targeting an
+    /// existing message must use [`Bot::request`], as message ids of the old
+    /// chat are unrelated to the ones of the supergroup.
+    async fn send_request<P, R>(
+        &self,
+        method: &str,
+        chat_id: Option<&str>,
+        payload: &P,
+    ) -> Result<R, Error>
+    where
+        P: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let mut payload = json_payload(payload)?;
+        let mut target = match payload.get("chat_id") {
+            Some(serde_json::Value::String(chat_id)) => self.migrations.resolve(chat_id),
+            Some(serde_json::Value::Number(chat_id)) => {
+                self.migrations.resolve(&chat_id.to_string())
+            }
+            _ => {
+                return self
+                    .execute(method, chat_id, |request| Ok(request.json(&payload)))
                     .await
-                    .map_err(|_| ErrorResult {
-                        code: StatusCode::ErrorInternalError.as_u16(),
-                        msg: "Error converting telegram error response to json".to_string(),
-                    })?;
-
-            Err(ErrorResult {
-                code: StatusCode::ErrorInternalError.as_u16(),
-                msg: err_result.description.to_owned(),
-            })
+            }
+        };
+
+        let mut migrated = false;
+        loop {
+            payload["chat_id"] = target.clone().into();
+            let limiter_chat_id = chat_id.map(|_| target.as_str());
+            let result = self
+                .execute(
+                    method,
+                    limiter_chat_id,
+                    |request| Ok(request.json(&payload)),
+                )
+                .await;
+
+            match result {
+                Err(err) if !migrated => match self.migrations.follow(&target, &err) {
+                    Some(to) => {
+                        target = to;
+                        migrated = true;
+                    }
+                    None => return Err(err),
+                },
+                result => return result,
+            }
+        }
+    }
+
+    /// Calls `method` of the Telegram Bot API.
+    ///
+    /// `chat_id` is the chat the request sends a message to, if any, and is
+    /// used for rate limiting. `build` fills the request body in and is called
+    /// once per attempt.
+    async fn execute<R, F>(&self, method: &str, chat_id: Option<&str>, build: F) -> Result<R, Error>
+    where
+        R: serde::de::DeserializeOwned,
+        F: Fn(reqwest::RequestBuilder) -> Result<reqwest::RequestBuilder, Error>,
+    {
+        let url = method_url(self.api_url(), self.token(), method);
+        let mut attempt = 1;
+        let mut flood_waits = 0;
+
+        loop {
+            let request = build(self.client.post(url.clone()))?;
+            if let (Some(limiter), Some(chat_id)) = (&self.rate_limiter, chat_id) {
+                limiter.acquire(chat_id).await;
+            }
+
+            let result = match request.send().await {
+                Ok(response) => handle_response(response).await,
+                Err(err) => Err(err.into()),
+            };
+
+            let err = match result {
+                Ok(result) => return Ok(result),
+                Err(err) => err,
+            };
+
+            // Waiting out flood control does not count as a retry attempt.
+            let flood_wait = match err.retry_after() {
+                Some(delay) if self.flood_wait && flood_waits < MAX_FLOOD_WAIT_RETRIES => {
+                    flood_waits += 1;
+                    Some(delay)
+                }
+                _ => None,
+            };
+
+            let delay = match flood_wait {
+                Some(delay) => delay,
+                None => match self.retry_policy.delay_for(&err, attempt) {
+                    Some(delay) => {
+                        attempt += 1;
+                        delay
+                    }
+                    None => return Err(err),
+                },
+            };
+
+            tokio::time::sleep(delay).await;
         }
     }
 
-    fn build_request_obj(&self, msg: &str, options: Option<SendMessageOption>) -> RequestObj {
-        let parse_mode = options
+    pub(crate) fn build_request_obj(
+        &self,
+        msg: &MessageText,
+        options: Option<SendMessageOption>,
+    ) -> RequestObj {
+        let parse_mode = parse_mode_str(options.as_ref()).map(str::to_owned);
+        let disable_notification = options
             .as_ref()
-            .and_then(|option| option.parse_mode.as_ref()) // Avoid repeated unwrap calls
-            .map(|mode| utils::get_send_message_parse_mode_str(mode).to_owned());
+            .is_some_and(|option| option.disable_notification);
+        let message_thread_id = options.as_ref().and_then(|option| option.message_thread_id);
+        let reply_markup = options.and_then(|option| option.reply_markup);
+
+        RequestObj::new(&self.chat_id, msg.text(), parse_mode)
+            .with_entities(msg.entities().map(<[_]>::to_vec))
+            .with_reply_markup(reply_markup)
+            .with_disable_notification(disable_notification)
+            .with_message_thread_id(message_thread_id)
+    }
+}
+
+/// Message Management
+impl Bot {
+    /// Edits the text of a message previously sent to the chat.
+    ///
+    /// Returns the edited [`Message`].
+    pub async fn edit_message_text(
+        &self,
+        message_id: MessageId,
+        text: &str,
+        options: Option<SendMessageOption>,
+    ) -> Result<Message, Error> {
+        if text.is_empty() {
+            return Err(Error::InvalidInput(
+                "message text must not be empty".to_owned(),
+            ));
+        }
+
+        let parse_mode = parse_mode_str(options.as_ref());
+        let payload = serde_json::json!({
+            "chat_id": self.chat_id(),
+            "message_id": message_id,
+            "text": text,
+            "parse_mode": parse_mode,
+            "reply_markup": options.and_then(|option| option.reply_markup),
+        });
+
+        self.request(EDIT_MESSAGE_TEXT_METHOD, Some(self.chat_id()), &payload)
+            .await
+    }
 
-        RequestObj::new(&self.chat_id, msg, parse_mode)
+    /// Edits the text of a message, formatted with `entities` instead of a
+    /// parse mode.
+    pub(crate) async fn edit_message_entities(
+        &self,
+        message_id: MessageId,
+        text: &str,
+        entities: &[MessageEntity],
+        reply_markup: Option<InlineKeyboardMarkup>,
+    ) -> Result<Message, Error> {
+        let payload = serde_json::json!({
+            "chat_id": self.chat_id(),
+            "message_id": message_id,
+            "text": text,
+            "entities": entities,
+            "reply_markup": reply_markup.unwrap_or_default(),
+        });
+
+        self.request(EDIT_MESSAGE_TEXT_METHOD, Some(self.chat_id()), &payload)
+            .await
+    }
+
+    /// Edits the caption of a message previously sent to the chat.
+    ///
+    /// Returns the edited [`Message`].
+    pub async fn edit_message_caption(
+        &self,
+        message_id: MessageId,
+        caption: &str,
+        options: Option<SendMessageOption>,
+    ) -> Result<Message, Error> {
+        let parse_mode = parse_mode_str(options.as_ref());
+        let payload = serde_json::json!({
+            "chat_id": self.chat_id(),
+            "message_id": message_id,
+            "caption": caption,
+            "parse_mode": parse_mode,
+            "reply_markup": options.and_then(|option| option.reply_markup),
+        });
+
+        self.request(EDIT_MESSAGE_CAPTION_METHOD, Some(self.chat_id()), &payload)
+            .await
+    }
+
+    /// Replaces the inline keyboard of a message previously sent to the chat.
+    /// `None` removes the keyboard.
+    ///
+    /// Returns the edited [`Message`].
+    pub async fn edit_message_reply_markup(
+        &self,
+        message_id: MessageId,
+        reply_markup: Option<InlineKeyboardMarkup>,
+    ) -> Result<Message, Error> {
+        let payload = serde_json::json!({
+            "chat_id": self.chat_id(),
+            "message_id": message_id,
+            "reply_markup": reply_markup.unwrap_or_default(),
+        });
+
+        self.request(
+            EDIT_MESSAGE_REPLY_MARKUP_METHOD,
+            Some(self.chat_id()),
+            &payload,
+        )
+        .await
+    }
+
+    /// Deletes a message from the chat.
+    pub async fn delete_message(&self, message_id: MessageId) -> Result<(), Error> {
+        let payload = serde_json::json!({
+            "chat_id": self.chat_id(),
+            "message_id": message_id,
+        });
+
+        self.request::<_, bool>(DELETE_MESSAGE_METHOD, None, &payload)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes several messages from the chat at once.
+    ///
+    /// Messages that cannot be found are skipped by Telegram. Any number of
+    /// messages can be passed, they are deleted in batches of 100.
+    pub async fn delete_messages(&self, message_ids: &[MessageId]) -> Result<(), Error> {
+        for batch in message_ids.chunks(DELETE_MESSAGES_LIMIT) {
+            let payload = serde_json::json!({
+                "chat_id": self.chat_id(),
+                "message_ids": batch,
+            });
+
+            self.request::<_, bool>(DELETE_MESSAGES_METHOD, None, &payload)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Pins a message in the chat. The bot must be an administrator with the
+    /// right to pin messages in groups and channels.
+    ///
+    /// If `disable_notification` is `true`, chat members are not notified
+    /// about the new pinned message.
+    pub async fn pin_chat_message(
+        &self,
+        message_id: MessageId,
+        disable_notification: bool,
+    ) -> Result<(), Error> {
+        let payload = serde_json::json!({
+            "chat_id": self.chat_id(),
+            "message_id": message_id,
+            "disable_notification": disable_notification,
+        });
+
+        self.request::<_, bool>(PIN_CHAT_MESSAGE_METHOD, None, &payload)
+            .await?;
+        Ok(())
+    }
+
+    /// Unpins a message in the chat. `None` unpins the most recently pinned
+    /// message.
+    pub async fn unpin_chat_message(&self, message_id: Option<MessageId>) -> Result<(), Error> {
+        let payload = serde_json::json!({
+            "chat_id": self.chat_id(),
+            "message_id": message_id,
+        });
+
+        self.request::<_, bool>(UNPIN_CHAT_MESSAGE_METHOD, None, &payload)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Forum Topics
+impl Bot {
+    /// Creates a topic in the chat, which must be a forum supergroup. The bot
+    /// needs the `can_manage_topics` administrator right.
+    ///
+    /// `icon_color` must be one of the colors allowed by Telegram, e.g.
+    /// `0x6FB9F0`. See [`TopicCache`] to reuse topics by name.
+    ///
+    /// [`TopicCache`]: crate::topics::TopicCache
+    pub async fn create_forum_topic(
+        &self,
+        name: &str,
+        icon_color: Option<u32>,
+    ) -> Result<ForumTopic, Error> {
+        let payload = serde_json::json!({
+            "chat_id": self.chat_id(),
+            "name": name,
+            "icon_color": icon_color,
+        });
+
+        self.request(CREATE_FORUM_TOPIC_METHOD, None, &payload)
+            .await
+    }
+
+    /// Renames a topic of the chat.
+    pub async fn edit_forum_topic(&self, message_thread_id: i32, name: &str) -> Result<(), Error> {
+        let payload = serde_json::json!({
+            "chat_id": self.chat_id(),
+            "message_thread_id": message_thread_id,
+            "name": name,
+        });
+
+        self.request::<_, bool>(EDIT_FORUM_TOPIC_METHOD, None, &payload)
+            .await?;
+        Ok(())
+    }
+
+    /// Closes an open topic of the chat.
+    pub async fn close_forum_topic(&self, message_thread_id: i32) -> Result<(), Error> {
+        self.forum_topic_request(CLOSE_FORUM_TOPIC_METHOD, message_thread_id)
+            .await
+    }
+
+    /// Reopens a closed topic of the chat.
+    pub async fn reopen_forum_topic(&self, message_thread_id: i32) -> Result<(), Error> {
+        self.forum_topic_request(REOPEN_FORUM_TOPIC_METHOD, message_thread_id)
+            .await
+    }
+
+    async fn forum_topic_request(&self, method: &str, message_thread_id: i32) -> Result<(), Error> {
+        let payload = serde_json::json!({
+            "chat_id": self.chat_id(),
+            "message_thread_id": message_thread_id,
+        });
+
+        self.request::<_, bool>(method, None, &payload).await?;
+        Ok(())
+    }
+}
+
+/// Updates
+impl Bot {
+    /// Receives incoming updates using long polling.
+    ///
+    /// Updates with an identifier lower than `offset` are confirmed and are
+    /// not returned again. If there are no updates, Telegram holds the
+    /// request for up to `timeout` before returning an empty list. The HTTP
+    /// timeout of the request is extended accordingly.
+    ///
+    /// See [`UpdateStream`] to receive updates continuously.
+    ///
+    /// [`UpdateStream`]: crate::polling::UpdateStream
+    pub async fn get_updates(
+        &self,
+        offset: Option<i64>,
+        timeout: Duration,
+    ) -> Result<Vec<Update>, Error> {
+        self.get_updates_as(offset, timeout).await
+    }
+
+    /// Receives incoming updates like [`Bot::get_updates`], decoded as `R`,
+    /// e.g. as [`serde_json::Value`] to decode them one by one.
+    pub(crate) async fn get_updates_as<R>(
+        &self,
+        offset: Option<i64>,
+        timeout: Duration,
+    ) -> Result<Vec<R>, Error>
+    where
+        R: serde::de::DeserializeOwned,
+    {
+        let payload = json_payload(&serde_json::json!({
+            "offset": offset,
+            "timeout": timeout.as_secs(),
+        }))?;
+
+        self.execute(GET_UPDATES_METHOD, None, |request| {
+            Ok(request
+                .json(&payload)
+                .timeout(timeout + GET_UPDATES_TIMEOUT_MARGIN))
+        })
+        .await
+    }
+
+    /// Tells Telegram to deliver updates to `url` instead of returning them
+    /// from [`Bot::get_updates`].
+    ///
+    /// If `secret_token` is set, Telegram sends it in the
+    /// `X-Telegram-Bot-Api-Secret-Token` header of every webhook request. If
+    /// `drop_pending_updates` is `true`, updates waiting for delivery are
+    /// dropped.
+    pub async fn set_webhook(
+        &self,
+        url: &str,
+        secret_token: Option<&str>,
+        drop_pending_updates: bool,
+    ) -> Result<(), Error> {
+        let payload = serde_json::json!({
+            "url": url,
+            "secret_token": secret_token,
+            "drop_pending_updates": drop_pending_updates,
+        });
+
+        self.request::<_, bool>(SET_WEBHOOK_METHOD, None, &payload)
+            .await?;
+        Ok(())
+    }
+
+    /// Removes the webhook, so that updates can be received with
+    /// [`Bot::get_updates`] again.
+    pub async fn delete_webhook(&self, drop_pending_updates: bool) -> Result<(), Error> {
+        let payload = serde_json::json!({
+            "drop_pending_updates": drop_pending_updates,
+        });
+
+        self.request::<_, bool>(DELETE_WEBHOOK_METHOD, None, &payload)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the current status of the webhook.
+    pub async fn get_webhook_info(&self) -> Result<WebhookInfo, Error> {
+        self.request(GET_WEBHOOK_INFO_METHOD, None, &serde_json::json!({}))
+            .await
+    }
+}
+
+/// Commands
+impl Bot {
+    /// Returns the user of the bot itself, e.g. to learn its username.
+    pub async fn get_me(&self) -> Result<User, Error> {
+        self.request(GET_ME_METHOD, None, &serde_json::json!({}))
+            .await
+    }
+
+    /// Replaces the list of commands shown in the command menu of Telegram
+    /// clients.
+    ///
+    /// See [`Dispatcher::register`] to register the commands of a dispatcher.
+    ///
+    /// [`Dispatcher::register`]: crate::dispatcher::Dispatcher::register
+    pub async fn set_my_commands(&self, commands: &[BotCommand]) -> Result<(), Error> {
+        let payload = serde_json::json!({
+            "commands": commands,
+        });
+
+        self.request::<_, bool>(SET_MY_COMMANDS_METHOD, None, &payload)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Callback Queries
+impl Bot {
+    /// Answers a callback query, which stops the progress indicator on the
+    /// pressed button.
+    ///
+    /// `text` is shown to the user as a notification at the top of the chat
+    /// screen, or as an alert if `show_alert` is `true`.
+    pub async fn answer_callback_query(
+        &self,
+        callback_query_id: &str,
+        text: Option<&str>,
+        show_alert: bool,
+    ) -> Result<(), Error> {
+        let payload = serde_json::json!({
+            "callback_query_id": callback_query_id,
+            "text": text,
+            "show_alert": show_alert,
+        });
+
+        self.request::<_, bool>(ANSWER_CALLBACK_QUERY_METHOD, None, &payload)
+            .await?;
+        Ok(())
+    }
+
+    /// Passes a callback query of a button created by this crate, e.g. of an
+    /// [`AckableAlert`], to the task waiting for it.
+    ///
+    /// Returns `false` if no task of this bot or its clones is waiting for
+    /// the query. [`Dispatcher`] calls this for every callback query, use it
+    /// when handling updates without a dispatcher.
+    ///
+    /// [`AckableAlert`]: crate::alert::AckableAlert
+    /// [`Dispatcher`]: crate::dispatcher::Dispatcher
+    pub fn route_callback_query(&self, query: &CallbackQuery) -> bool {
+        self.callbacks.route(query)
+    }
+
+    /// Sends `prompt` with "Approve" and "Reject" buttons, and waits up to
+    /// `timeout` for one of the approvers set with [`Bot::with_approvers`] to
+    /// press one.
+    ///
+    /// Presses by other users are rejected. Once decided or timed out, the
+    /// buttons are replaced by a line telling who decided and when. Callback
+    /// queries must be passed to [`Bot::route_callback_query`], e.g. by a
+    /// [`Dispatcher`].
+    ///
+    /// Returns [`Error::InvalidInput`] if no approvers are set.
+    ///
+    /// [`Dispatcher`]: crate::dispatcher::Dispatcher
+    pub async fn ask_confirmation(
+        &self,
+        prompt: &str,
+        timeout: Duration,
+    ) -> Result<Decision, Error> {
+        confirmation::ask(self, self.approvers(), prompt, timeout).await
+    }
+
+    pub(crate) fn callbacks(&self) -> &Arc<Callbacks> {
+        &self.callbacks
     }
 }
 
@@ -209,12 +1081,62 @@ impl Bot {
         &self.token
     }
 
+    /// Returns the chat messages are sent to, see [`Bot::for_chat`] to send to
+    /// other chats.
+    ///
+    /// This is the chat the `Bot` was created with, even once it has been
+    /// upgraded to a supergroup: the new id is applied when sending, see
+    /// [`Bot::migrated_chat_id`].
+    #[must_use]
+    pub fn chat_id(&self) -> &str {
+        &self.chat_id
+    }
+
+    /// Returns the id of the supergroup the chat of this `Bot` has been
+    /// upgraded to, if any. New messages are sent there instead of to
+    /// [`Bot::chat_id`].
+    #[must_use]
+    pub fn migrated_chat_id(&self) -> Option<i64> {
+        self.migrations.get(&self.chat_id)
+    }
+
+    /// Returns the ids of the users allowed to answer
+    /// [`Bot::ask_confirmation`].
+    #[must_use]
+    pub fn approvers(&self) -> &[u64] {
+        &self.approvers
+    }
+
+    /// Returns how many messages [`Bot::broadcast`] sends at the same time.
+    #[must_use]
+    pub fn broadcast_concurrency(&self) -> usize {
+        self.broadcast_concurrency
+    }
+
     /// Returns currently used http-client.
     #[must_use]
     pub fn client(&self) -> &Client {
         &self.client
     }
 
+    /// Returns whether flood control is waited out automatically.
+    #[must_use]
+    pub fn flood_wait(&self) -> bool {
+        self.flood_wait
+    }
+
+    /// Returns currently used retry policy.
+    #[must_use]
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Returns currently used rate limiter, if any.
+    #[must_use]
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_deref()
+    }
+
     /// Returns currently used token API url.
     #[must_use]
     pub fn api_url(&self) -> reqwest::Url {
@@ -222,7 +1144,7 @@ impl Bot {
     }
 }
 
-/// ********************** Utilities **********************
+// ********************** Utilities **********************
 
 /// Returns a reqwest client builder with default settings.
 ///
@@ -239,6 +1161,8 @@ impl Bot {
 /// 1. The settings may change in the future.
 /// 2. If you are using the polling mechanism to get updates, the timeout
 ///    configured in the client should be bigger than the polling timeout.
+///    [`Bot::get_updates`] takes care of it by extending the timeout of its
+///    own requests.
 /// 3. If you alter the current settings listed above, your bot will not be
 ///    guaranteed to work over long time durations.
 ///
@@ -250,6 +1174,57 @@ fn default_reqwest_settings() -> reqwest::ClientBuilder {
         .tcp_nodelay(true)
 }
 
+/// Serializes the parameters of a request.
+///
+/// Top-level `null` parameters are left out, so that optional parameters can
+/// be passed as `Option`s.
+fn json_payload<P>(payload: &P) -> Result<serde_json::Value, Error>
+where
+    P: serde::Serialize,
+{
+    let mut payload = serde_json::to_value(payload)?;
+    if let serde_json::Value::Object(params) = &mut payload {
+        params.retain(|_, value| !value.is_null());
+    }
+
+    Ok(payload)
+}
+
+/// Returns the parse mode set in `options`, if any, as expected by Telegram.
+fn parse_mode_str(options: Option<&SendMessageOption>) -> Option<&'static str> {
+    options
+        .and_then(|option| option.parse_mode.as_ref())
+        .map(utils::get_send_message_parse_mode_str)
+}
+
+/// Turns a response of the Telegram Bot API into a `Result`.
+///
+/// If Telegram's error body cannot be parsed (e.g. a proxy answered instead
+/// of Telegram), the HTTP status is reported as the error code.
+async fn handle_response<R>(response: reqwest::Response) -> Result<R, Error>
+where
+    R: serde::de::DeserializeOwned,
+{
+    let status = response.status();
+    if status.is_success() {
+        let body = response.bytes().await?;
+        let response_obj: ResponseObj<R> = serde_json::from_slice(&body)?;
+        return Ok(response_obj.result);
+    }
+
+    match response.json::<TelegramErrorResult>().await {
+        Ok(err_result) => Err(err_result.into()),
+        Err(_) => Err(Error::Api {
+            error_code: i32::from(status.as_u16()),
+            description: status
+                .canonical_reason()
+                .unwrap_or("Unknown error")
+                .to_owned(),
+            parameters: None,
+        }),
+    }
+}
+
 /// Creates URL for making HTTPS requests. See the [Telegram documentation].
 ///
 /// [Telegram documentation]: https://core.telegram.org/bots/api#making-requests
@@ -260,7 +1235,13 @@ fn method_url(base: reqwest::Url, token: &str, method_name: &str) -> reqwest::Ur
 
 #[cfg(test)]
 mod tests {
-    use crate::bot::{method_url, SEND_MESSAGE_METHOD, TELEGRAM_API_URL};
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        bot::{method_url, Bot, SEND_MESSAGE_METHOD, TELEGRAM_API_URL},
+        errors::Error,
+        types::{ChatId, ResponseParameters},
+    };
 
     #[test]
     fn method_url_test() {
@@ -278,4 +1259,40 @@ mod tests {
             )
         );
     }
+
+    #[tokio::test]
+    async fn invalid_mime_type_is_invalid_input() {
+        let bot = Bot::new("token", "-1");
+        let result = bot
+            .send_document(b"a,b".to_vec(), "report.csv", "not a mime", "".into(), None)
+            .await;
+
+        assert!(matches!(result, Err(Error::InvalidInput(_))), "{result:?}");
+    }
+
+    #[test]
+    fn migrations_are_shared_with_earlier_clones() {
+        let bot = Bot::new("token", "-123");
+        let earlier = bot.clone();
+
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let bot = bot.with_migration_hook({
+            let reported = Arc::clone(&reported);
+            move |from: &ChatId, to| reported.lock().unwrap().push((from.clone(), to))
+        });
+
+        let migrated = Error::Api {
+            error_code: 400,
+            description: "Bad Request: group chat was upgraded to a supergroup chat".to_owned(),
+            parameters: Some(ResponseParameters {
+                migrate_to_chat_id: Some(-1001234),
+                retry_after: None,
+            }),
+        };
+        earlier.migrations.follow("-123", &migrated);
+
+        assert_eq!(*reported.lock().unwrap(), [(ChatId::Id(-123), -1001234)]);
+        assert_eq!(bot.migrated_chat_id(), Some(-1001234));
+        assert_eq!(bot.chat_id(), "-123");
+    }
 }
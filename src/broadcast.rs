@@ -0,0 +1,359 @@
+use futures_util::stream::{self, StreamExt};
+
+use crate::{
+    bot::MAX_FLOOD_WAIT_RETRIES,
+    errors::Error,
+    migration::migrate_to_chat_id,
+    requester::Requester,
+    throttle::RateLimiter,
+    types::{ChatId, Message, MessageId, SendMessageOption},
+};
+
+/// Default number of messages [`Bot::broadcast`] sends at the same time.
+///
+/// [`Bot::broadcast`]: crate::bot::Bot::broadcast
+pub const DEFAULT_BROADCAST_CONCURRENCY: usize = 8;
+
+/// How [`send_all`] paces the messages of a broadcast, in addition to the
+/// pacing of the bot itself.
+#[derive(Debug)]
+pub(crate) struct Pacing {
+    /// Limits the messages sent per second across all chats.
+    pub(crate) limiter: Option<RateLimiter>,
+
+    /// Whether to wait out flood control and send to the chat again.
+    pub(crate) flood_wait: bool,
+}
+
+/// Outcome of a [`Bot::broadcast`], per chat.
+///
+/// [`Bot::broadcast`]: crate::bot::Bot::broadcast
+#[derive(Debug, Default)]
+pub struct BroadcastReport {
+    /// Delivery status of every chat, in the order the chats were given.
+    pub results: Vec<(ChatId, DeliveryStatus)>,
+}
+
+/// Delivery status of a message sent to one chat of a broadcast.
+#[derive(Debug)]
+pub enum DeliveryStatus {
+    /// The message was delivered.
+    Delivered(MessageId),
+
+    /// The bot was blocked by the user, or removed from the chat.
+    Blocked,
+
+    /// The chat does not exist, or the bot has never been in it.
+    ChatNotFound,
+
+    /// The group was upgraded to a supergroup with the new id `to` while
+    /// sending, and `status` is the outcome of sending to the supergroup.
+    Migrated {
+        to: i64,
+        status: Box<DeliveryStatus>,
+    },
+
+    /// Sending failed for another reason.
+    Failed(Error),
+}
+
+impl BroadcastReport {
+    /// Returns the number of chats the message was delivered to.
+    #[must_use]
+    pub fn delivered(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, status)| status.is_delivered())
+            .count()
+    }
+
+    /// Returns the chats the message was not delivered to, with the reason.
+    pub fn failures(&self) -> impl Iterator<Item = &(ChatId, DeliveryStatus)> {
+        self.results
+            .iter()
+            .filter(|(_, status)| !status.is_delivered())
+    }
+
+    /// Returns the chats which have been upgraded to supergroups, with the id
+    /// of the supergroup.
+    pub fn migrations(&self) -> impl Iterator<Item = (&ChatId, i64)> {
+        self.results
+            .iter()
+            .filter_map(|(chat_id, status)| match status {
+                DeliveryStatus::Migrated { to, .. } => Some((chat_id, *to)),
+                _ => None,
+            })
+    }
+}
+
+impl DeliveryStatus {
+    /// Returns whether the message was delivered, possibly to the supergroup
+    /// the chat was upgraded to.
+    #[must_use]
+    pub fn is_delivered(&self) -> bool {
+        match self {
+            Self::Delivered(_) => true,
+            Self::Migrated { status, .. } => status.is_delivered(),
+            _ => false,
+        }
+    }
+}
+
+impl From<Result<Message, Error>> for DeliveryStatus {
+    fn from(result: Result<Message, Error>) -> Self {
+        let err = match result {
+            Ok(message) => return Self::Delivered(message.id),
+            Err(err) => err,
+        };
+
+        // The supergroup has been upgraded again, which is not followed.
+        if let Some(to) = migrate_to_chat_id(&err) {
+            return Self::Migrated {
+                to,
+                status: Box::new(Self::Failed(err)),
+            };
+        }
+
+        match &err {
+            Error::Api {
+                error_code: 403, ..
+            } => Self::Blocked,
+            Error::Api {
+                error_code: 400,
+                description,
+                ..
+            } if description.contains("chat not found") => Self::ChatNotFound,
+            _ => Self::Failed(err),
+        }
+    }
+}
+
+/// Sends `msg` to every chat of `chats` through `bot`, up to `concurrency`
+/// at the same time. See [`Bot::broadcast`].
+///
+/// [`Bot::broadcast`]: crate::bot::Bot::broadcast
+pub(crate) async fn send_all<B>(
+    bot: &B,
+    chats: Vec<ChatId>,
+    msg: &str,
+    options: Option<SendMessageOption>,
+    concurrency: usize,
+    pacing: &Pacing,
+) -> BroadcastReport
+where
+    B: Requester,
+{
+    let sends = chats.into_iter().map(|chat_id| {
+        let bot = bot.for_chat(chat_id.clone());
+        let options = options.clone();
+
+        async move {
+            let status = send_one(&bot, &chat_id, msg, options, pacing).await;
+            (chat_id, status)
+        }
+    });
+
+    let results = stream::iter(sends).buffered(concurrency).collect().await;
+    BroadcastReport { results }
+}
+
+/// Sends `msg` to the chat of `bot`, and reports a migration of the chat
+/// followed while sending.
+async fn send_one<B>(
+    bot: &B,
+    chat_id: &ChatId,
+    msg: &str,
+    options: Option<SendMessageOption>,
+    pacing: &Pacing,
+) -> DeliveryStatus
+where
+    B: Requester,
+{
+    let migrated_before = bot.migrated_chat_id();
+    let mut flood_waits = 0;
+
+    let result = loop {
+        if let Some(limiter) = &pacing.limiter {
+            limiter.acquire(&chat_id.to_string()).await;
+        }
+
+        let err = match bot.send_message(msg, options.clone()).await {
+            Ok(message) => break Ok(message),
+            Err(err) => err,
+        };
+
+        match err.retry_after() {
+            Some(delay) if pacing.flood_wait && flood_waits < MAX_FLOOD_WAIT_RETRIES => {
+                flood_waits += 1;
+                tokio::time::sleep(delay).await;
+            }
+            _ => break Err(err),
+        }
+    };
+
+    let status = DeliveryStatus::from(result);
+    match bot.migrated_chat_id() {
+        Some(to) if migrated_before != Some(to) => DeliveryStatus::Migrated {
+            to,
+            status: Box::new(status),
+        },
+        _ => status,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use crate::{
+        broadcast::{send_all, DeliveryStatus, Pacing},
+        errors::Error,
+        requester::mock::{api_error, retry_after, MockBot, Request},
+        throttle::{Limits, RateLimiter},
+        types::{ChatId, Message, ResponseParameters},
+    };
+
+    fn status(err: Error) -> DeliveryStatus {
+        Err::<Message, _>(err).into()
+    }
+
+    fn blocked() -> Error {
+        api_error(403, "Forbidden: bot was blocked by the user", None)
+    }
+
+    fn migrated(to: i64) -> Error {
+        api_error(
+            400,
+            "Bad Request: group chat was upgraded to a supergroup chat",
+            Some(ResponseParameters {
+                migrate_to_chat_id: Some(to),
+                retry_after: None,
+            }),
+        )
+    }
+
+    fn chats(ids: &[i64]) -> Vec<ChatId> {
+        ids.iter().copied().map(ChatId::Id).collect()
+    }
+
+    fn sent_chats(bot: &MockBot) -> Vec<String> {
+        bot.take_requests()
+            .into_iter()
+            .filter_map(|request| match request {
+                Request::SendMessage { chat_id, .. } => Some(chat_id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn unpaced() -> Pacing {
+        Pacing {
+            limiter: None,
+            flood_wait: false,
+        }
+    }
+
+    #[test]
+    fn errors_are_classified() {
+        assert!(matches!(status(blocked()), DeliveryStatus::Blocked));
+        assert!(matches!(
+            status(api_error(400, "Bad Request: chat not found", None)),
+            DeliveryStatus::ChatNotFound
+        ));
+        assert!(matches!(
+            status(migrated(-1001234)),
+            DeliveryStatus::Migrated { to: -1001234, .. }
+        ));
+        assert!(matches!(
+            status(api_error(400, "Bad Request: message text is empty", None)),
+            DeliveryStatus::Failed(_)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn chats_are_reported_in_order_and_sent_concurrently() {
+        let bot = MockBot::new("0").with_latency(Duration::from_secs(1));
+        bot.fail_next_with(blocked());
+
+        let start = Instant::now();
+        let report = send_all(&bot, chats(&[1, 2, 3, 4]), "Halted", None, 2, &unpaced()).await;
+
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+        assert_eq!(sent_chats(&bot), ["1", "2", "3", "4"]);
+        assert_eq!(report.delivered(), 3);
+
+        let statuses = report
+            .results
+            .iter()
+            .map(|(chat_id, status)| (chat_id.to_string(), status.is_delivered()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                ("1".to_owned(), false),
+                ("2".to_owned(), true),
+                ("3".to_owned(), true),
+                ("4".to_owned(), true),
+            ]
+        );
+        assert!(matches!(report.results[0].1, DeliveryStatus::Blocked));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn migrations_are_reported_once() {
+        let bot = MockBot::new("0");
+        bot.fail_next_with(migrated(-1001));
+        bot.fail_next_with(blocked());
+
+        let report = send_all(&bot, chats(&[-1]), "Halted", None, 1, &unpaced()).await;
+        assert_eq!(sent_chats(&bot), ["-1", "-1001"]);
+        match &report.results[0].1 {
+            DeliveryStatus::Migrated { to, status } => {
+                assert_eq!(*to, -1001);
+                assert!(matches!(**status, DeliveryStatus::Blocked));
+            }
+            status => panic!("unexpected status {status:?}"),
+        }
+        assert_eq!(report.migrations().count(), 1);
+
+        // The chat has been migrated by the earlier broadcast.
+        let report = send_all(&bot, chats(&[-1]), "Halted", None, 1, &unpaced()).await;
+        assert_eq!(sent_chats(&bot), ["-1001"]);
+        assert!(matches!(report.results[0].1, DeliveryStatus::Delivered(_)));
+        assert_eq!(report.migrations().count(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn broadcasts_are_paced_and_wait_out_flood_control() {
+        let bot = MockBot::new("0");
+        let pacing = Pacing {
+            limiter: Some(RateLimiter::new(Limits {
+                messages_per_sec_chat: 0,
+                messages_per_min_group: 0,
+                ..Limits::default()
+            })),
+            flood_wait: true,
+        };
+
+        let start = Instant::now();
+        let ids = (1..=31).collect::<Vec<_>>();
+        let report = send_all(&bot, chats(&ids), "Halted", None, 8, &pacing).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        assert_eq!(report.delivered(), 31);
+        bot.take_requests();
+
+        bot.fail_next_with(api_error(
+            429,
+            "Too Many Requests: retry after 5",
+            retry_after(5),
+        ));
+
+        let start = Instant::now();
+        let report = send_all(&bot, chats(&[1]), "Halted", None, 8, &pacing).await;
+        assert!(start.elapsed() >= Duration::from_secs(5));
+        assert_eq!(sent_chats(&bot), ["1", "1"]);
+        assert_eq!(report.delivered(), 1);
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use tokio::sync::mpsc;
+
+use crate::types::CallbackQuery;
+
+/// Prefix of the callback data of buttons created by this crate.
+const CALLBACK_DATA_PREFIX: &str = "rg";
+
+/// Routes callback queries of buttons created by this crate, e.g. of alerts
+/// and confirmations, to the task waiting for them.
+///
+/// The callback data of such buttons is `rg:<token>:<action>`, where the
+/// token identifies the waiting task.
+#[derive(Debug, Default)]
+pub(crate) struct Callbacks {
+    routes: Mutex<HashMap<String, mpsc::UnboundedSender<CallbackQuery>>>,
+}
+
+/// A route registered with [`Callbacks`], removed again once dropped.
+#[derive(Debug)]
+pub(crate) struct Registration {
+    callbacks: Arc<Callbacks>,
+    token: String,
+    queries: mpsc::UnboundedReceiver<CallbackQuery>,
+}
+
+impl Callbacks {
+    /// Registers a new route with a unique token.
+    pub(crate) fn register(self: &Arc<Self>) -> Registration {
+        let (sender, queries) = mpsc::unbounded_channel();
+
+        let mut routes = self.lock();
+        let token = loop {
+            let token = format!("{:016x}", fastrand::u64(..));
+            if !routes.contains_key(&token) {
+                break token;
+            }
+        };
+        routes.insert(token.clone(), sender);
+
+        Registration {
+            callbacks: Arc::clone(self),
+            token,
+            queries,
+        }
+    }
+
+    /// Passes `query` to the task waiting for it, and returns whether there
+    /// is such a task.
+    pub(crate) fn route(&self, query: &CallbackQuery) -> bool {
+        let token = match query.data.as_deref().and_then(parse_callback_data) {
+            Some((token, _)) => token,
+            None => return false,
+        };
+
+        let mut routes = self.lock();
+        match routes.get(token) {
+            Some(sender) if sender.send(query.clone()).is_ok() => true,
+            Some(_) => {
+                routes.remove(token);
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, mpsc::UnboundedSender<CallbackQuery>>> {
+        self.routes.lock().expect("callback routes are poisoned")
+    }
+}
+
+impl Registration {
+    /// Returns the callback data of a button triggering `action`.
+    pub(crate) fn data(&self, action: &str) -> String {
+        format!("{CALLBACK_DATA_PREFIX}:{}:{action}", self.token)
+    }
+
+    /// Waits for the next callback query of this route.
+    pub(crate) async fn recv(&mut self) -> Option<CallbackQuery> {
+        self.queries.recv().await
+    }
+
+    /// Returns the next callback query of this route, if one is waiting.
+    pub(crate) fn try_recv(&mut self) -> Option<CallbackQuery> {
+        self.queries.try_recv().ok()
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.callbacks.lock().remove(&self.token);
+    }
+}
+
+/// Returns the action of a callback query routed by [`Callbacks`].
+pub(crate) fn callback_action(query: &CallbackQuery) -> Option<&str> {
+    query
+        .data
+        .as_deref()
+        .and_then(parse_callback_data)
+        .map(|(_, action)| action)
+}
+
+/// Splits callback data into the token and the action.
+fn parse_callback_data(data: &str) -> Option<(&str, &str)> {
+    let mut parts = data.splitn(3, ':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(CALLBACK_DATA_PREFIX), Some(token), Some(action)) => Some((token, action)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        callbacks::{callback_action, Callbacks},
+        requester::mock::query,
+    };
+
+    #[tokio::test]
+    async fn queries_are_routed_by_token() {
+        let callbacks = Arc::new(Callbacks::default());
+        let mut registration = callbacks.register();
+
+        let data = registration.data("ack");
+        assert!(callbacks.route(&query(7, &data)));
+        assert!(!callbacks.route(&query(7, "rg:unknown:ack")));
+        assert!(!callbacks.route(&query(7, "custom")));
+
+        let received = registration.recv().await.unwrap();
+        assert_eq!(callback_action(&received), Some("ack"));
+
+        drop(registration);
+        assert!(!callbacks.route(&query(7, &data)));
+    }
+}
@@ -0,0 +1,260 @@
+use std::time::{Duration, SystemTime};
+
+use crate::{
+    callbacks::{callback_action, Registration},
+    errors::Error,
+    requester::Requester,
+    types::{CallbackQuery, InlineKeyboardButton, InlineKeyboardMarkup, SendMessageOption, User},
+    utils,
+};
+
+const APPROVE_ACTION: &str = "approve";
+const REJECT_ACTION: &str = "reject";
+
+/// Shown to users who are not allowed to answer a confirmation.
+const NOT_ALLOWED: &str = "You are not allowed to answer this request";
+
+/// Shown to approvers pressing a button this version does not know.
+const UNKNOWN_ACTION: &str = "Unknown answer, press Approve or Reject";
+
+/// Shown to users pressing a button once the confirmation timed out.
+const EXPIRED: &str = "This request has expired";
+
+/// Shown to users pressing a button once the confirmation is decided.
+const ALREADY_DECIDED: &str = "This request has already been answered";
+
+/// Answer to a confirmation requested with [`Bot::ask_confirmation`].
+///
+/// [`Bot::ask_confirmation`]: crate::bot::Bot::ask_confirmation
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Decision {
+    /// An approver pressed "Approve".
+    Approved { by: User },
+
+    /// An approver pressed "Reject".
+    Rejected { by: User },
+
+    /// No approver answered in time.
+    TimedOut,
+}
+
+impl Decision {
+    /// Returns whether the request was approved.
+    #[must_use]
+    pub fn is_approved(&self) -> bool {
+        matches!(self, Self::Approved { .. })
+    }
+
+    /// Returns the line shown below the prompt once the decision is made.
+    fn status_line(&self) -> String {
+        let now = utils::format_utc(SystemTime::now());
+        match self {
+            Self::Approved { by } => {
+                format!("✅ Approved by {} at {now}", utils::display_name(by))
+            }
+            Self::Rejected { by } => {
+                format!("❌ Rejected by {} at {now}", utils::display_name(by))
+            }
+            Self::TimedOut => format!("⌛ Expired at {now} without a decision"),
+        }
+    }
+}
+
+/// Sends `prompt` with "Approve" and "Reject" buttons, and waits up to
+/// `timeout` for one of the `approvers` to press one.
+pub(crate) async fn ask<B>(
+    bot: &B,
+    approvers: &[u64],
+    prompt: &str,
+    timeout: Duration,
+) -> Result<Decision, Error>
+where
+    B: Requester,
+{
+    if approvers.is_empty() {
+        return Err(Error::InvalidInput(
+            "no approvers are configured, see Bot::with_approvers".to_owned(),
+        ));
+    }
+
+    let mut registration = bot.callbacks().register();
+    let options = SendMessageOption {
+        reply_markup: Some(keyboard(&registration).into()),
+        ..Default::default()
+    };
+    let message = bot.send_message(prompt, Some(options)).await?;
+
+    let decision = tokio::time::timeout(
+        timeout,
+        wait_for_decision(bot, approvers, &mut registration),
+    )
+    .await
+    .unwrap_or(Decision::TimedOut);
+
+    // The decision stands even if the prompt could not be updated.
+    let text = format!(
+        "{}\n\n{}",
+        message.text.as_deref().unwrap_or(prompt),
+        decision.status_line()
+    );
+    let _ = bot
+        .edit_message_entities(message.id, &text, &message.entities, None)
+        .await;
+
+    // Buttons pressed until the prompt was updated are answered, instead of
+    // leaving the client waiting for an answer.
+    let late = match decision {
+        Decision::TimedOut => EXPIRED,
+        _ => ALREADY_DECIDED,
+    };
+    while let Some(query) = registration.try_recv() {
+        let _ = bot.answer_callback_query(&query.id, Some(late), true).await;
+    }
+
+    Ok(decision)
+}
+
+async fn wait_for_decision<B>(
+    bot: &B,
+    approvers: &[u64],
+    registration: &mut Registration,
+) -> Decision
+where
+    B: Requester,
+{
+    loop {
+        let query = match registration.recv().await {
+            Some(query) => query,
+            None => return std::future::pending().await,
+        };
+
+        match decision(approvers, &query) {
+            Ok(decision) => {
+                let _ = bot.answer_callback_query(&query.id, None, false).await;
+                return decision;
+            }
+            Err(reason) => {
+                let _ = bot
+                    .answer_callback_query(&query.id, Some(reason), true)
+                    .await;
+            }
+        }
+    }
+}
+
+/// Returns the decision made by a button press, or the reason shown to the
+/// user if it was not pressed by one of the `approvers`, or is not a known
+/// answer.
+fn decision(approvers: &[u64], query: &CallbackQuery) -> Result<Decision, &'static str> {
+    if !approvers.contains(&query.from.id) {
+        return Err(NOT_ALLOWED);
+    }
+
+    let by = query.from.clone();
+    match callback_action(query) {
+        Some(APPROVE_ACTION) => Ok(Decision::Approved { by }),
+        Some(REJECT_ACTION) => Ok(Decision::Rejected { by }),
+        _ => Err(UNKNOWN_ACTION),
+    }
+}
+
+fn keyboard(registration: &Registration) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new([[
+        InlineKeyboardButton::callback("Approve", registration.data(APPROVE_ACTION)),
+        InlineKeyboardButton::callback("Reject", registration.data(REJECT_ACTION)),
+    ]])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        confirmation::{ask, decision, Decision, EXPIRED, NOT_ALLOWED, UNKNOWN_ACTION},
+        requester::mock::{query, user, MockBot, Request},
+        types::MessageId,
+    };
+
+    const TIMEOUT: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn only_approvers_decide() {
+        let approvers = [7, 9];
+
+        assert_eq!(
+            decision(&approvers, &query(8, "rg:token:approve")),
+            Err(NOT_ALLOWED)
+        );
+        assert!(matches!(
+            decision(&approvers, &query(7, "rg:token:approve")),
+            Ok(Decision::Approved { by }) if by.id == 7
+        ));
+        assert!(matches!(
+            decision(&approvers, &query(9, "rg:token:reject")),
+            Ok(Decision::Rejected { by }) if by.id == 9
+        ));
+        assert_eq!(
+            decision(&approvers, &query(7, "rg:token:unknown")),
+            Err(UNKNOWN_ACTION)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn approval_updates_prompt() {
+        let bot = MockBot::new("-100");
+        let asked = tokio::spawn({
+            let bot = bot.clone();
+            async move { ask(&bot, &[7], "Deploy v2?", TIMEOUT).await }
+        });
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        assert!(bot.press("Approve", &user(8)));
+        assert!(bot.press("Approve", &user(7)));
+
+        let decision = asked.await.unwrap().unwrap();
+        assert_eq!(decision, Decision::Approved { by: user(7) });
+        match &bot.take_requests()[..] {
+            [Request::SendMessage { text, .. }, Request::AnswerCallbackQuery { text: rejected, .. }, Request::AnswerCallbackQuery { text: answered, .. }, Request::EditMessageEntities {
+                message_id,
+                text: edited,
+                reply_markup,
+                ..
+            }] => {
+                assert_eq!(text, "Deploy v2?");
+                assert_eq!(rejected.as_deref(), Some(NOT_ALLOWED));
+                assert_eq!(*answered, None);
+                assert_eq!(*message_id, MessageId(1));
+                assert!(edited.starts_with("Deploy v2?\n\n✅ Approved by @alice at "));
+                assert_eq!(*reply_markup, None);
+            }
+            requests => panic!("unexpected requests: {requests:?}"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn presses_after_timeout_are_answered() {
+        let bot = MockBot::new("-100").with_latency(Duration::from_secs(1));
+        let asked = tokio::spawn({
+            let bot = bot.clone();
+            async move { ask(&bot, &[7], "Deploy v2?", TIMEOUT).await }
+        });
+
+        // The prompt is sent after a second, and updated a second after the
+        // timeout.
+        tokio::time::sleep(TIMEOUT + Duration::from_millis(1500)).await;
+        assert!(bot.press("Approve", &user(7)));
+
+        assert_eq!(asked.await.unwrap().unwrap(), Decision::TimedOut);
+        match &bot.take_requests()[..] {
+            [Request::SendMessage { .. }, Request::EditMessageEntities {
+                text, reply_markup, ..
+            }, Request::AnswerCallbackQuery { text: answer, .. }] => {
+                assert!(text.starts_with("Deploy v2?\n\n⌛ Expired at "));
+                assert_eq!(*reply_markup, None);
+                assert_eq!(answer.as_deref(), Some(EXPIRED));
+            }
+            requests => panic!("unexpected requests: {requests:?}"),
+        }
+        assert!(!bot.press("Approve", &user(7)));
+    }
+}
@@ -0,0 +1,406 @@
+use std::{collections::HashMap, fmt, future::Future, pin::Pin};
+
+use futures_util::stream::{Stream, StreamExt};
+use tokio::task::JoinSet;
+
+use crate::{
+    bot::Bot,
+    errors::Error,
+    types::{BotCommand, Chat, Message, Update},
+};
+
+type HandlerFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+type Handler = Box<dyn Fn(Command) -> HandlerFuture + Send + Sync>;
+
+/// Routes commands like `/status` or `/pause strategy_x` received by a bot to
+/// async handlers.
+///
+/// Only commands sent to the chat of the [`Bot`] are handled, so that nobody
+/// else can control the services behind the bot. Further chats can be allowed
+/// with [`Dispatcher::allow_chat`].
+///
+/// ```no_run
+/// # async fn run(bot: rustygram::bot::Bot) -> Result<(), rustygram::Error> {
+/// use rustygram::{dispatcher::Dispatcher, polling::UpdateStream};
+/// use std::time::Duration;
+///
+/// let replies = bot.clone();
+/// let mut dispatcher = Dispatcher::new(bot.clone()).command(
+///     "pause",
+///     "Pause a strategy",
+///     move |command| {
+///         let replies = replies.clone();
+///         async move {
+///             let strategy = command.arg(0).unwrap_or("all");
+///             let _ = replies.send_message(&format!("Paused {strategy}"), None).await;
+///         }
+///     },
+/// );
+///
+/// dispatcher.register().await?;
+/// dispatcher.run(UpdateStream::new(bot, Duration::from_secs(10))).await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Dispatcher {
+    bot: Bot,
+    username: Option<String>,
+    allowed_chats: Vec<i64>,
+    commands: Vec<BotCommand>,
+    handlers: HashMap<String, Handler>,
+}
+
+/// A command received by a [`Dispatcher`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Command {
+    /// Name of the command, without the leading `/` and the bot username.
+    pub name: String,
+
+    /// Whitespace separated arguments following the command.
+    pub args: Vec<String>,
+
+    /// The message containing the command.
+    pub message: Message,
+}
+
+impl Dispatcher {
+    /// Creates a new `Dispatcher` handling commands sent to the chat of
+    /// `bot`.
+    pub fn new(bot: Bot) -> Self {
+        Self {
+            bot,
+            username: None,
+            allowed_chats: Vec::new(),
+            commands: Vec::new(),
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Sets the username of the bot, so that commands addressed to other
+    /// bots, e.g. `/status@otherbot`, are ignored.
+    ///
+    /// Unless set, the username is looked up by [`Dispatcher::register`].
+    /// Without a username, commands are handled whichever bot they are
+    /// addressed to.
+    pub fn with_username<U>(mut self, username: U) -> Self
+    where
+        U: Into<String>,
+    {
+        let username = username.into();
+        self.username = Some(username.trim_start_matches('@').to_owned());
+        self
+    }
+
+    /// Handles commands sent to the chat `chat_id` as well.
+    pub fn allow_chat(mut self, chat_id: i64) -> Self {
+        self.allowed_chats.push(chat_id);
+        self
+    }
+
+    /// Adds a command, handled by calling `handler`. Registering the same
+    /// command again replaces it.
+    ///
+    /// `command` is given without the leading `/`, and must consist of 1-32
+    /// lowercase English letters, digits and underscores to be accepted by
+    /// [`Dispatcher::register`].
+    pub fn command<F, Fut>(mut self, command: &str, description: &str, handler: F) -> Self
+    where
+        F: Fn(Command) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let command = command.trim_start_matches('/').to_lowercase();
+
+        self.commands.retain(|existing| existing.command != command);
+        self.commands
+            .push(BotCommand::new(command.as_str(), description));
+        self.handlers
+            .insert(command, Box::new(move |command| Box::pin(handler(command))));
+        self
+    }
+
+    /// Returns the commands added to the dispatcher.
+    #[must_use]
+    pub fn commands(&self) -> &[BotCommand] {
+        &self.commands
+    }
+
+    /// Registers the commands with `setMyCommands`, so that Telegram clients
+    /// suggest them, and looks up the username of the bot unless it is set.
+    pub async fn register(&mut self) -> Result<(), Error> {
+        self.bot.set_my_commands(&self.commands).await?;
+
+        if self.username.is_none() {
+            self.username = self.bot.get_me().await?.username;
+        }
+        Ok(())
+    }
+
+    /// Handles a single update, and returns whether it was a command of this
+    /// dispatcher.
+    ///
+    /// Waits until the handler completes, so a handler waiting for a button
+    /// press, e.g. with [`Bot::ask_confirmation`], must not be dispatched
+    /// this way. Callback queries of buttons created by this crate are passed
+    /// to [`Bot::route_callback_query`].
+    pub async fn dispatch(&self, update: &Update) -> bool {
+        if let Some(query) = &update.callback_query {
+            return self.bot.route_callback_query(query);
+        }
+
+        match self.handler(update) {
+            Some(handler) => {
+                handler.await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Handles `updates` until the stream ends, and then waits for the
+    /// handlers still running.
+    ///
+    /// Every command is handled in a task of its own, while callback queries
+    /// are routed as they arrive, so that a handler can wait for a button
+    /// press, e.g. with [`Bot::ask_confirmation`]. Commands are thus handled
+    /// concurrently. A panic of a handler is propagated.
+    pub async fn run<S>(&self, updates: S)
+    where
+        S: Stream<Item = Update>,
+    {
+        tokio::pin!(updates);
+        let mut handlers = JoinSet::new();
+
+        loop {
+            tokio::select! {
+                update = updates.next() => {
+                    let update = match update {
+                        Some(update) => update,
+                        None => break,
+                    };
+
+                    if let Some(query) = &update.callback_query {
+                        self.bot.route_callback_query(query);
+                    } else if let Some(handler) = self.handler(&update) {
+                        handlers.spawn(handler);
+                    }
+                }
+                Some(result) = handlers.join_next(), if !handlers.is_empty() => {
+                    propagate_panic(result);
+                }
+            }
+        }
+
+        while let Some(result) = handlers.join_next().await {
+            propagate_panic(result);
+        }
+    }
+
+    /// Returns the handler of the command in `update`, if it is a command of
+    /// this dispatcher sent to an allowed chat.
+    fn handler(&self, update: &Update) -> Option<HandlerFuture> {
+        let message = match update.message.as_ref().or(update.channel_post.as_ref()) {
+            Some(message) if self.is_allowed(&message.chat) => message,
+            _ => return None,
+        };
+
+        let (name, args) = message
+            .text
+            .as_deref()
+            .and_then(|text| parse_command(text, self.username.as_deref()))?;
+        let handler = self.handlers.get(&name)?;
+
+        Some(handler(Command {
+            name,
+            args,
+            message: message.clone(),
+        }))
+    }
+
+    fn is_allowed(&self, chat: &Chat) -> bool {
+        let chat_id = self.bot.chat_id();
+
+        let is_bot_chat = match (chat_id.strip_prefix('@'), &chat.username) {
+            (Some(name), Some(username)) => name.eq_ignore_ascii_case(username),
+            (Some(_), None) => false,
+            (None, _) => chat_id == chat.id.to_string(),
+        };
+
+        is_bot_chat
+            || self.bot.migrated_chat_id() == Some(chat.id)
+            || self.allowed_chats.contains(&chat.id)
+    }
+}
+
+impl Command {
+    /// Returns the argument at `index`, if present.
+    #[must_use]
+    pub fn arg(&self, index: usize) -> Option<&str> {
+        self.args.get(index).map(String::as_str)
+    }
+}
+
+impl fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dispatcher")
+            .field("username", &self.username)
+            .field("allowed_chats", &self.allowed_chats)
+            .field("commands", &self.commands)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Resumes the panic of a handler task, if it panicked.
+fn propagate_panic(result: Result<(), tokio::task::JoinError>) {
+    if let Err(err) = result {
+        if err.is_panic() {
+            std::panic::resume_unwind(err.into_panic());
+        }
+    }
+}
+
+/// Splits `text` into the lowercased command name and its arguments.
+///
+/// Returns `None` if `text` is not a command, or if it is addressed to a bot
+/// other than `username`.
+fn parse_command(text: &str, username: Option<&str>) -> Option<(String, Vec<String>)> {
+    let mut words = text.split_whitespace();
+    let command = words.next()?.strip_prefix('/')?;
+
+    let (name, addressee) = match command.split_once('@') {
+        Some((name, addressee)) => (name, Some(addressee)),
+        None => (command, None),
+    };
+
+    let addressed_elsewhere = matches!(
+        (addressee, username),
+        (Some(addressee), Some(username)) if !addressee.eq_ignore_ascii_case(username)
+    );
+    if name.is_empty() || addressed_elsewhere {
+        return None;
+    }
+
+    Some((name.to_lowercase(), words.map(str::to_owned).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use futures_util::stream;
+    use tokio::sync::mpsc;
+
+    use crate::{
+        bot::Bot,
+        dispatcher::{parse_command, Dispatcher},
+        types::Update,
+    };
+
+    fn update(chat_id: i64, text: &str) -> Update {
+        serde_json::from_value(serde_json::json!({
+            "update_id": 1,
+            "message": {
+                "message_id": 2,
+                "date": 1700000000,
+                "chat": { "id": chat_id, "type": "group", "title": "Desk" },
+                "text": text,
+            },
+        }))
+        .unwrap()
+    }
+
+    fn callback_update(data: &str) -> Update {
+        serde_json::from_value(serde_json::json!({
+            "update_id": 3,
+            "callback_query": {
+                "id": "query",
+                "from": { "id": 7, "is_bot": false, "first_name": "Trader" },
+                "chat_instance": "instance",
+                "data": data,
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn commands_are_parsed() {
+        let parse = |text| parse_command(text, Some("mybot"));
+
+        assert_eq!(parse("/status"), Some(("status".to_owned(), vec![])));
+        assert_eq!(
+            parse("/pause@MyBot  strategy_x now"),
+            Some((
+                "pause".to_owned(),
+                vec!["strategy_x".to_owned(), "now".to_owned()]
+            ))
+        );
+        assert_eq!(parse("/status@otherbot"), None);
+        assert_eq!(parse("status"), None);
+        assert_eq!(parse("/ status"), None);
+        assert_eq!(
+            parse_command("/status@otherbot", None),
+            Some(("status".to_owned(), vec![]))
+        );
+    }
+
+    #[tokio::test]
+    async fn commands_are_dispatched_from_allowed_chats() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let dispatcher = {
+            let received = Arc::clone(&received);
+            Dispatcher::new(Bot::new("token", "-100"))
+                .allow_chat(-200)
+                .command("pause", "Pause a strategy", move |command| {
+                    let received = Arc::clone(&received);
+                    async move { received.lock().unwrap().push(command.args) }
+                })
+        };
+
+        assert!(dispatcher.dispatch(&update(-100, "/pause a")).await);
+        assert!(dispatcher.dispatch(&update(-200, "/pause b")).await);
+        assert!(!dispatcher.dispatch(&update(-300, "/pause c")).await);
+        assert!(!dispatcher.dispatch(&update(-100, "/resume a")).await);
+        assert_eq!(*received.lock().unwrap(), [["a"], ["b"]]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn handlers_can_wait_for_button_presses() {
+        let bot = Bot::new("token", "-100");
+        let (buttons_tx, mut buttons) = mpsc::unbounded_channel();
+        let pressed = Arc::new(Mutex::new(Vec::new()));
+
+        let dispatcher = {
+            let pressed = Arc::clone(&pressed);
+            let bot = bot.clone();
+            Dispatcher::new(bot.clone()).command("confirm", "Confirm", move |_| {
+                let mut registration = bot.callbacks().register();
+                let buttons_tx = buttons_tx.clone();
+                let pressed = Arc::clone(&pressed);
+                async move {
+                    buttons_tx.send(registration.data("yes")).unwrap();
+                    let query = registration.recv().await.unwrap();
+                    pressed.lock().unwrap().push(query.data.unwrap());
+                }
+            })
+        };
+
+        let (updates_tx, updates) = mpsc::unbounded_channel();
+        let updates = stream::unfold(updates, |mut updates| async move {
+            updates.recv().await.map(|update| (update, updates))
+        });
+        let run = tokio::spawn(async move { dispatcher.run(updates).await });
+
+        updates_tx.send(update(-100, "/confirm")).unwrap();
+        let data = buttons.recv().await.unwrap();
+        updates_tx.send(callback_update(&data)).unwrap();
+        drop(updates_tx);
+
+        tokio::time::timeout(Duration::from_secs(60), run)
+            .await
+            .expect("the button press is not routed")
+            .unwrap();
+        assert_eq!(*pressed.lock().unwrap(), [data]);
+    }
+}
@@ -1,12 +1,41 @@
-use std::fmt::{Display, Error, Formatter};
+use std::{error, fmt, io, time::Duration};
 
-use reqwest::{multipart, StatusCode};
-/// ErrorResult usually returned to indicate result from calling APIs related
-/// functions.
+use crate::types::ResponseParameters;
+
+/// An error that can occur while calling the Telegram Bot API.
+///
+/// Returned by every [`Bot`] method, so that callers can branch on the cause
+/// of a failure instead of inspecting the error message.
+///
+/// [`Bot`]: crate::bot::Bot
 #[derive(Debug)]
-pub struct ErrorResult {
-    pub code: u16,   // error returned code
-    pub msg: String, // error string description
+pub enum Error {
+    /// Transport level error, e.g. the connection failed or timed out.
+    Network(reqwest::Error),
+
+    /// I/O error, e.g. a file to upload could not be read.
+    Io(io::Error),
+
+    /// A request could not be serialized or a response could not be
+    /// deserialized.
+    Json(serde_json::Error),
+
+    /// Telegram processed the request and returned an error.
+    ///
+    /// See <https://core.telegram.org/bots/api#making-requests>
+    Api {
+        /// Error code, it mirrors the HTTP status code of the response.
+        error_code: i32,
+
+        /// Human-readable description of the error.
+        description: String,
+
+        /// Additional information on how the request can be retried.
+        parameters: Option<ResponseParameters>,
+    },
+
+    /// The input was rejected before any request was made.
+    InvalidInput(String),
 }
 
 /// Telegram's error result.
@@ -17,27 +46,111 @@ pub struct TelegramErrorResult {
     pub ok: bool,
     pub error_code: i32,
     pub description: String,
+    pub parameters: Option<ResponseParameters>,
 }
 
-impl Display for ErrorResult {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        write!(f, "{}", self.msg)
+impl Error {
+    /// Returns how long to wait before repeating the request, if Telegram's
+    /// flood control rejected it.
+    #[must_use]
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::Api {
+                parameters: Some(parameters),
+                ..
+            } => parameters
+                .retry_after
+                .map(|secs| Duration::from_secs(u64::from(secs))),
+            _ => None,
+        }
     }
 }
-impl From<std::io::Error> for ErrorResult {
-    fn from(err: std::io::Error) -> Self {
-        ErrorResult {
-            code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-            msg: format!("IO Error: {}", err),
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(err) => write!(f, "HTTP request error: {err}"),
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+            Self::Json(err) => write!(f, "JSON error: {err}"),
+            Self::Api {
+                error_code,
+                description,
+                ..
+            } => write!(f, "Telegram API error {error_code}: {description}"),
+            Self::InvalidInput(msg) => write!(f, "Invalid input: {msg}"),
         }
     }
 }
 
-impl From<reqwest::Error> for ErrorResult {
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Network(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            Self::Api { .. } | Self::InvalidInput(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<reqwest::Error> for Error {
     fn from(err: reqwest::Error) -> Self {
-        ErrorResult {
-            code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-            msg: format!("HTTP Request Error: {}", err),
+        Self::Network(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<TelegramErrorResult> for Error {
+    fn from(err: TelegramErrorResult) -> Self {
+        Self::Api {
+            error_code: err.error_code,
+            description: err.description,
+            parameters: err.parameters,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::errors::{Error, TelegramErrorResult};
+
+    #[test]
+    fn telegram_error_result_into_api_error() {
+        let raw = r#"{
+            "ok": false,
+            "error_code": 429,
+            "description": "Too Many Requests: retry after 5",
+            "parameters": { "retry_after": 5 }
+        }"#;
+
+        let err: Error = serde_json::from_str::<TelegramErrorResult>(raw)
+            .unwrap()
+            .into();
+
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(5)));
+        match err {
+            Error::Api {
+                error_code,
+                parameters,
+                ..
+            } => {
+                assert_eq!(error_code, 429);
+                assert_eq!(parameters.unwrap().retry_after, Some(5));
+            }
+            other => panic!("unexpected error: {other:?}"),
         }
     }
 }
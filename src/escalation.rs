@@ -0,0 +1,516 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use tokio::{
+    sync::{watch, Notify},
+    time::Instant,
+};
+
+use crate::{
+    bot::Bot,
+    callbacks::{callback_action, Registration},
+    errors::Error,
+    requester::Requester,
+    types::{
+        CallbackQuery, ChatId, InlineKeyboardButton, InlineKeyboardMarkup, Message,
+        SendMessageOption, User,
+    },
+    utils,
+};
+
+const ACK_ACTION: &str = "ack";
+
+/// Steps an [`Escalation`] goes through while its alert is not acknowledged.
+///
+/// ```
+/// use rustygram::escalation::EscalationPolicy;
+/// use std::time::Duration;
+///
+/// let policy = EscalationPolicy::new()
+///     .resend_after(Duration::from_secs(5 * 60))
+///     .escalate_to(Duration::from_secs(10 * 60), [-1001234567890, 123456789]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct EscalationPolicy {
+    steps: Vec<EscalationStep>,
+}
+
+/// A step of an [`EscalationPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EscalationStep {
+    /// Time to wait for an acknowledgement since the previous message of the
+    /// escalation was sent.
+    pub after: Duration,
+
+    /// Chats the alert is sent to, the chat of the bot if empty. Users can be
+    /// targeted by their id, once they have started a chat with the bot.
+    pub chats: Vec<ChatId>,
+}
+
+/// An alert with an "Ack" button, which is sent again following an
+/// [`EscalationPolicy`] until somebody acknowledges it.
+///
+/// The first message is sent with the given options, every escalation step
+/// sends it with notifications enabled. Pressing "Ack" on any of the messages
+/// stops the escalation and edits all of them to show who acknowledged and
+/// when.
+///
+/// Callback queries must be passed to the bot, either by a [`Dispatcher`] or
+/// by [`Bot::route_callback_query`]. The "Ack" button keeps working after the
+/// `Escalation` is dropped, until the alert is acknowledged or resolved with
+/// [`Escalation::resolve`].
+///
+/// [`Dispatcher`]: crate::dispatcher::Dispatcher
+#[derive(Debug, Clone)]
+pub struct Escalation {
+    state: watch::Receiver<EscalationState>,
+    resolved: Arc<Notify>,
+}
+
+/// State of an [`Escalation`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EscalationState {
+    /// Nobody has acknowledged the alert yet.
+    Unacknowledged {
+        /// Number of escalation steps taken so far.
+        steps_taken: usize,
+    },
+
+    /// The alert has been acknowledged, escalation has stopped.
+    Acknowledged { by: User, at: SystemTime },
+
+    /// The alert has been resolved with [`Escalation::resolve`] before
+    /// anybody acknowledged it, escalation has stopped.
+    Resolved { at: SystemTime },
+}
+
+/// Sends the escalation steps and tracks the acknowledgement.
+struct Engine<B> {
+    bot: B,
+    registration: Registration,
+    state: watch::Sender<EscalationState>,
+    resolved: Arc<Notify>,
+    policy: EscalationPolicy,
+    text: String,
+    options: SendMessageOption,
+
+    /// Messages sent so far, with the bot sending to their chat.
+    sent: Vec<(B, Message)>,
+}
+
+impl EscalationPolicy {
+    /// Creates a new `EscalationPolicy` without steps, i.e. the alert is sent
+    /// only once.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a step sending the alert to the chat of the bot again, if it is
+    /// not acknowledged within `after`.
+    #[must_use]
+    pub fn resend_after(self, after: Duration) -> Self {
+        self.escalate_to(after, Vec::<ChatId>::new())
+    }
+
+    /// Adds a step sending the alert to `chats`, if it is not acknowledged
+    /// within `after`.
+    #[must_use]
+    pub fn escalate_to<I, C>(mut self, after: Duration, chats: I) -> Self
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<ChatId>,
+    {
+        self.steps.push(EscalationStep {
+            after,
+            chats: chats.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Returns the steps of the policy.
+    #[must_use]
+    pub fn steps(&self) -> &[EscalationStep] {
+        &self.steps
+    }
+}
+
+impl Escalation {
+    /// Sends `text` as an alert to the chat of `bot`, and escalates it
+    /// according to `policy` until it is acknowledged.
+    ///
+    /// The reply markup of `options` is replaced by the "Ack" button.
+    pub async fn send(
+        bot: &Bot,
+        text: &str,
+        options: Option<SendMessageOption>,
+        policy: EscalationPolicy,
+    ) -> Result<Self, Error> {
+        Self::start(bot, text, options, policy).await
+    }
+
+    async fn start<B>(
+        bot: &B,
+        text: &str,
+        options: Option<SendMessageOption>,
+        policy: EscalationPolicy,
+    ) -> Result<Self, Error>
+    where
+        B: Requester,
+    {
+        let registration = bot.callbacks().register();
+        let options = SendMessageOption {
+            reply_markup: Some(keyboard(&registration).into()),
+            ..options.unwrap_or_default()
+        };
+
+        let message = bot.send_message(text, Some(options.clone())).await?;
+
+        let (sender, state) = watch::channel(EscalationState::Unacknowledged { steps_taken: 0 });
+        let resolved = Arc::new(Notify::new());
+        let engine = Engine {
+            bot: bot.clone(),
+            registration,
+            state: sender,
+            resolved: Arc::clone(&resolved),
+            policy,
+            text: text.to_owned(),
+            options: SendMessageOption {
+                disable_notification: false,
+                ..options
+            },
+            sent: vec![(bot.clone(), message)],
+        };
+        tokio::spawn(engine.run());
+
+        Ok(Self { state, resolved })
+    }
+
+    /// Stops the escalation without an acknowledgement, e.g. once the
+    /// condition raising the alert has cleared, and edits all messages to
+    /// show when it was resolved.
+    ///
+    /// Resolving an acknowledged alert has no effect.
+    pub fn resolve(&self) {
+        self.resolved.notify_one();
+    }
+
+    /// Returns the current state of the escalation.
+    #[must_use]
+    pub fn state(&self) -> EscalationState {
+        self.state.borrow().clone()
+    }
+
+    /// Returns a channel receiving every change of the escalation state.
+    #[must_use]
+    pub fn subscribe(&self) -> watch::Receiver<EscalationState> {
+        self.state.clone()
+    }
+
+    /// Waits until the alert is acknowledged or resolved.
+    pub async fn acknowledged(&self) -> EscalationState {
+        let mut state = self.state.clone();
+        let result = state
+            .wait_for(|state| !matches!(state, EscalationState::Unacknowledged { .. }))
+            .await
+            .map(|state| state.clone());
+
+        match result {
+            Ok(state) => state,
+            Err(_) => state.borrow().clone(),
+        }
+    }
+}
+
+impl<B> Engine<B>
+where
+    B: Requester,
+{
+    /// Escalates until the alert is acknowledged or resolved.
+    ///
+    /// The callback route is removed once this returns.
+    async fn run(mut self) {
+        let resolved = Arc::clone(&self.resolved);
+        let mut steps_taken = 0;
+        let mut deadline = self.deadline(steps_taken);
+
+        loop {
+            let step_due = async move {
+                match deadline {
+                    Some(due) => tokio::time::sleep_until(due).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                query = self.registration.recv() => match query {
+                    Some(query) if self.handle(&query).await => return,
+                    Some(_) => (),
+                    None => return,
+                },
+                () = step_due => {
+                    self.escalate(steps_taken).await;
+                    steps_taken += 1;
+                    deadline = self.deadline(steps_taken);
+                    self.state
+                        .send_replace(EscalationState::Unacknowledged { steps_taken });
+                }
+                () = resolved.notified() => {
+                    self.finish(EscalationState::Resolved {
+                        at: SystemTime::now(),
+                    })
+                    .await;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Returns when the step `index` is due, if there is such a step.
+    fn deadline(&self, index: usize) -> Option<Instant> {
+        self.policy
+            .steps
+            .get(index)
+            .map(|step| Instant::now() + step.after)
+    }
+
+    /// Sends the alert to the chats of the step `index`.
+    async fn escalate(&mut self, index: usize) {
+        let step = &self.policy.steps[index];
+        let bots = if step.chats.is_empty() {
+            vec![self.bot.clone()]
+        } else {
+            step.chats
+                .iter()
+                .map(|chat| self.bot.for_chat(chat.clone()))
+                .collect()
+        };
+
+        // A chat which cannot be reached must not stop the escalation.
+        for bot in bots {
+            if let Ok(message) = bot
+                .send_message(&self.text, Some(self.options.clone()))
+                .await
+            {
+                self.sent.push((bot, message));
+            }
+        }
+    }
+
+    /// Handles a button press, and returns whether it acknowledged the alert.
+    async fn handle(&mut self, query: &CallbackQuery) -> bool {
+        if callback_action(query) != Some(ACK_ACTION) {
+            let _ = self.bot.answer_callback_query(&query.id, None, false).await;
+            return false;
+        }
+
+        let state = EscalationState::Acknowledged {
+            by: query.from.clone(),
+            at: SystemTime::now(),
+        };
+        let status = state.status_line();
+        let _ = self
+            .bot
+            .answer_callback_query(&query.id, status.as_deref(), false)
+            .await;
+
+        self.finish(state).await;
+        true
+    }
+
+    /// Edits all messages to show the final `state`, without the button, and
+    /// publishes it.
+    async fn finish(&mut self, state: EscalationState) {
+        let status = state.status_line();
+        for (bot, message) in &self.sent {
+            let text = match (&message.text, &status) {
+                (Some(text), Some(status)) => format!("{text}\n\n{status}"),
+                _ => continue,
+            };
+            let _ = bot
+                .edit_message_entities(message.id, &text, &message.entities, None)
+                .await;
+        }
+
+        self.state.send_replace(state);
+    }
+}
+
+impl EscalationState {
+    /// Returns the line shown below the alert once it is acknowledged or
+    /// resolved.
+    #[must_use]
+    pub fn status_line(&self) -> Option<String> {
+        match self {
+            Self::Unacknowledged { .. } => None,
+            Self::Acknowledged { by, at } => Some(format!(
+                "✅ Acknowledged by {} at {}",
+                utils::display_name(by),
+                utils::format_utc(*at)
+            )),
+            Self::Resolved { at } => Some(format!("☑️ Resolved at {}", utils::format_utc(*at))),
+        }
+    }
+}
+
+fn keyboard(registration: &Registration) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new([[InlineKeyboardButton::callback(
+        "Ack",
+        registration.data(ACK_ACTION),
+    )]])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        escalation::{Escalation, EscalationPolicy, EscalationState},
+        requester::mock::{user, MockBot, Request},
+        types::{ChatId, MessageId, SendMessageOption},
+    };
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    /// Returns the chats the alert has been sent to, and whether with a
+    /// notification.
+    fn sent(requests: &[Request]) -> Vec<(&str, bool)> {
+        requests
+            .iter()
+            .filter_map(|request| match request {
+                Request::SendMessage {
+                    chat_id, options, ..
+                } => Some((
+                    chat_id.as_str(),
+                    !options.as_ref().unwrap().disable_notification,
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    async fn escalation(bot: &MockBot) -> Escalation {
+        let options = SendMessageOption {
+            disable_notification: true,
+            ..Default::default()
+        };
+        let policy = EscalationPolicy::new()
+            .resend_after(5 * MINUTE)
+            .escalate_to(10 * MINUTE, ["-200", "42"]);
+
+        Escalation::start(bot, "Disk full", Some(options), policy)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn policy_lists_steps_in_order() {
+        let policy = EscalationPolicy::new()
+            .resend_after(Duration::from_secs(300))
+            .escalate_to(Duration::from_secs(600), ["-100200", "42"]);
+
+        let steps = policy.steps();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].after, Duration::from_secs(300));
+        assert!(steps[0].chats.is_empty());
+        assert_eq!(steps[1].chats, [ChatId::Id(-100200), ChatId::Id(42)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn steps_are_sent_when_due() {
+        let bot = MockBot::new("-100");
+        let escalation = escalation(&bot).await;
+        assert_eq!(sent(&bot.take_requests()), [("-100", false)]);
+
+        tokio::time::sleep(5 * MINUTE - Duration::from_secs(1)).await;
+        assert_eq!(bot.take_requests(), []);
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(sent(&bot.take_requests()), [("-100", true)]);
+        assert_eq!(
+            escalation.state(),
+            EscalationState::Unacknowledged { steps_taken: 1 }
+        );
+
+        tokio::time::sleep(10 * MINUTE - Duration::from_secs(2)).await;
+        assert_eq!(bot.take_requests(), []);
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(sent(&bot.take_requests()), [("-200", true), ("42", true)]);
+        assert_eq!(
+            escalation.state(),
+            EscalationState::Unacknowledged { steps_taken: 2 }
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ack_stops_escalation_and_edits_all_messages() {
+        let bot = MockBot::new("-100");
+        let escalation = escalation(&bot).await;
+        tokio::time::sleep(5 * MINUTE + Duration::from_secs(1)).await;
+        assert_eq!(sent(&bot.take_requests()).len(), 2);
+
+        assert!(bot.press("Ack", &user(7)));
+        let state = escalation.acknowledged().await;
+        let status = state.status_line().unwrap();
+        assert!(status.starts_with("✅ Acknowledged by @alice at "));
+
+        let requests = bot.take_requests();
+        assert!(matches!(
+            &requests[0],
+            Request::AnswerCallbackQuery { text, .. } if text.as_deref() == Some(status.as_str())
+        ));
+        let edited: Vec<_> = requests[1..]
+            .iter()
+            .map(|request| match request {
+                Request::EditMessageEntities {
+                    chat_id,
+                    message_id,
+                    text,
+                    ..
+                } => {
+                    assert_eq!(*text, format!("Disk full\n\n{status}"));
+                    (chat_id.as_str(), *message_id)
+                }
+                request => panic!("unexpected request: {request:?}"),
+            })
+            .collect();
+        assert_eq!(edited, [("-100", MessageId(1)), ("-100", MessageId(2))]);
+
+        // The second step would have been due 10 minutes after the first.
+        tokio::time::sleep(60 * MINUTE).await;
+        assert_eq!(bot.take_requests(), []);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resolve_stops_escalation_and_removes_route() {
+        let bot = MockBot::new("-100");
+        let escalation = escalation(&bot).await;
+        bot.take_requests();
+
+        escalation.resolve();
+        let state = escalation.acknowledged().await;
+        assert!(matches!(state, EscalationState::Resolved { .. }));
+
+        let status = state.status_line().unwrap();
+        assert!(status.starts_with("☑️ Resolved at "));
+        match &bot.take_requests()[..] {
+            [Request::EditMessageEntities {
+                message_id,
+                text,
+                reply_markup,
+                ..
+            }] => {
+                assert_eq!(*message_id, MessageId(1));
+                assert_eq!(*text, format!("Disk full\n\n{status}"));
+                assert_eq!(*reply_markup, None);
+            }
+            requests => panic!("unexpected requests: {requests:?}"),
+        }
+
+        assert!(!bot.press("Ack", &user(7)));
+        tokio::time::sleep(60 * MINUTE).await;
+        assert_eq!(bot.take_requests(), []);
+    }
+}
@@ -21,7 +21,7 @@ fn create_bot() -> Bot {
 
 #[tokio::main]
 /// Send a message to the chat associated with the bot.
-fn send_message() -> Result<(), ErrorResult> {
+fn send_message() -> Result<(), Error> {
     let bot = create_bot();
     let msg = "Hello, world!";
     let options = None;
@@ -13,11 +13,34 @@
 /// The `msg` parameter is the text of the message to send, and the `options` parameter can be
 /// used to specify additional options like parse mode.
 use bot::Bot;
+pub mod alert;
 pub mod bot;
+pub mod broadcast;
+mod callbacks;
+pub mod confirmation;
+pub mod dispatcher;
 pub mod errors;
+pub mod escalation;
+pub mod long_text;
+mod markup;
+mod migration;
+pub mod notifier;
+pub mod outbox;
+pub mod polling;
+mod requester;
+pub mod retry;
+pub mod rich_text;
+pub mod router;
+pub mod status_board;
 pub mod tests;
+pub mod throttle;
+pub mod topics;
 pub mod types;
 pub mod utils;
+#[cfg(feature = "webhook")]
+pub mod webhook;
+
+pub use errors::Error;
 
 /// Create a Bot to interact with APIs.
 /// Returns a `Bot` configured with the provided bot token and chat id.
@@ -30,7 +53,7 @@ pub fn create_bot(bot_token: &str, chat_id: &str) -> Bot {
 }
 
 /// Send message asynchronously.
-/// Return `Result<(), ErrorResult>`.
+/// Return `Result<Message, Error>`.
 ///
 /// # Arguments
 ///
@@ -41,6 +64,6 @@ pub async fn send_message(
     bot: &Bot,
     msg: &str,
     options: Option<types::SendMessageOption>,
-) -> Result<(), errors::ErrorResult> {
+) -> Result<types::Message, Error> {
     bot.send_message(msg, options).await
 }
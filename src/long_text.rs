@@ -0,0 +1,530 @@
+use std::ops::Range;
+
+use crate::{
+    errors::Error,
+    rich_text::RichText,
+    types::{MessageEntity, MessageText, SendMessageParseMode},
+};
+
+/// Maximum length of the text of a message, in UTF-16 code units.
+pub const MESSAGE_LENGTH_LIMIT: usize = 4096;
+
+/// Maximum length of the caption of a document, in UTF-16 code units.
+pub const CAPTION_LENGTH_LIMIT: usize = 1024;
+
+/// Default length of the preview of a text sent as a document.
+pub const DEFAULT_PREVIEW_LENGTH: usize = 300;
+
+/// Appended to the preview of a text sent as a document.
+const ELLIPSIS: &str = "…";
+
+/// What is done with text longer than Telegram accepts, see
+/// [`MESSAGE_LENGTH_LIMIT`] and [`CAPTION_LENGTH_LIMIT`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum LongText {
+    /// The text is sent as is, and rejected by Telegram.
+    #[default]
+    Reject,
+
+    /// The text is split into several messages, sent in order.
+    ///
+    /// The text is split at paragraphs, lines or words, outside of
+    /// formatting if possible and never inside of code blocks or links unless
+    /// they are too long by themselves. Formatting cut by a split is
+    /// continued in the next message. If `numbered` is set, every message
+    /// ends with its number, like `(1/3)`.
+    Split { numbered: bool },
+
+    /// The text is sent as a text file, if it is longer than the threshold
+    /// of [`TextDocument`], with the beginning of the text as caption.
+    ///
+    /// The file contains the text without formatting and is always sent as
+    /// `text/plain`, whatever the extension of its name. Reply markup is not
+    /// supported and ignored.
+    Document(Box<TextDocument>),
+}
+
+/// Settings of [`LongText::Document`].
+///
+/// ```
+/// use rustygram::long_text::{LongText, TextDocument};
+///
+/// let long_text = LongText::from(TextDocument::new("crash.log").with_threshold(1000));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextDocument {
+    /// Name of the file, e.g. `orderbook.txt` or `crash.log`.
+    pub file_name: String,
+
+    /// Length in UTF-16 code units above which the text is sent as a file,
+    /// at most [`MESSAGE_LENGTH_LIMIT`] as longer text can't be sent as a
+    /// message.
+    pub threshold: usize,
+
+    /// Length in UTF-16 code units of the preview sent as caption, at most
+    /// [`CAPTION_LENGTH_LIMIT`].
+    pub preview_length: usize,
+}
+
+/// Boundaries the text can be split at, the preferred ones first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Boundary {
+    Paragraph,
+    Line,
+    Word,
+    Char,
+}
+
+/// Entities a split can cut, the preferred case first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Cuts {
+    Nothing,
+    Formatting,
+    Anything,
+}
+
+/// Kinds of split points which are tried in order, until one is found in
+/// the part.
+const SPLIT_LEVELS: [(Boundary, Cuts); 9] = [
+    (Boundary::Paragraph, Cuts::Nothing),
+    (Boundary::Line, Cuts::Nothing),
+    (Boundary::Word, Cuts::Nothing),
+    (Boundary::Paragraph, Cuts::Formatting),
+    (Boundary::Line, Cuts::Formatting),
+    (Boundary::Word, Cuts::Formatting),
+    (Boundary::Line, Cuts::Anything),
+    (Boundary::Word, Cuts::Anything),
+    (Boundary::Char, Cuts::Anything),
+];
+
+/// Splits `text` into parts of at most `limit` UTF-16 code units, see
+/// [`LongText::Split`].
+///
+/// `text` is formatted with `parse_mode` unless it has entities. It is
+/// returned as is if it is short enough, otherwise the parts are formatted
+/// with entities. Fails if no part is left once whitespace is trimmed.
+pub(crate) fn split(
+    text: &MessageText,
+    parse_mode: Option<SendMessageParseMode>,
+    limit: usize,
+    numbered: bool,
+) -> Result<Vec<MessageText>, Error> {
+    // Markup only makes the text longer.
+    if text.text().encode_utf16().count() <= limit {
+        return Ok(vec![text.clone()]);
+    }
+
+    let rich_text = to_rich_text(text, parse_mode)?;
+    if rich_text.len_utf16() <= limit {
+        return Ok(vec![text.clone()]);
+    }
+
+    let parts = split_rich_text(&rich_text, limit, numbered);
+    if parts.is_empty() {
+        return Err(Error::InvalidInput(
+            "message text must not be blank".to_owned(),
+        ));
+    }
+
+    Ok(parts.into_iter().map(MessageText::from).collect())
+}
+
+/// Splits `text` into a first part of at most `limit` UTF-16 code units and
+/// the rest, which is empty if `text` is short enough.
+pub(crate) fn split_first(
+    text: &MessageText,
+    parse_mode: Option<SendMessageParseMode>,
+    limit: usize,
+) -> Result<(MessageText, RichText), Error> {
+    if text.text().encode_utf16().count() <= limit {
+        return Ok((text.clone(), RichText::new()));
+    }
+
+    let rich_text = to_rich_text(text, parse_mode)?;
+    if rich_text.len_utf16() <= limit {
+        return Ok((text.clone(), RichText::new()));
+    }
+
+    let chars = utf16_chars(rich_text.text());
+    let end = split_point(&chars, rich_text.entities(), 0, limit);
+    let (first, rest) = trimmed(&chars, 0, end, chars.len());
+
+    Ok((rich_text.slice(first).into(), rich_text.slice(rest)))
+}
+
+/// Returns the preview and the contents of the file `text` is sent as
+/// according to `document`, or `None` if it is not longer than the threshold.
+pub(crate) fn text_document(
+    text: &MessageText,
+    parse_mode: Option<SendMessageParseMode>,
+    document: &TextDocument,
+) -> Result<Option<(MessageText, String)>, Error> {
+    let threshold = document.threshold.min(MESSAGE_LENGTH_LIMIT);
+    if text.text().encode_utf16().count() <= threshold {
+        return Ok(None);
+    }
+
+    let rich_text = to_rich_text(text, parse_mode)?;
+    if rich_text.len_utf16() <= threshold {
+        return Ok(None);
+    }
+
+    let preview_length = document
+        .preview_length
+        .min(CAPTION_LENGTH_LIMIT)
+        .saturating_sub(ELLIPSIS.encode_utf16().count())
+        .max(2);
+    let chars = utf16_chars(rich_text.text());
+    let end = split_point(&chars, rich_text.entities(), 0, preview_length);
+    let (preview, _) = trimmed(&chars, 0, end, chars.len());
+
+    let preview = rich_text.slice(preview).plain(ELLIPSIS);
+    Ok(Some((preview.into(), rich_text.text().to_owned())))
+}
+
+/// Returns the text and entities `text` is formatted with.
+pub(crate) fn to_rich_text(
+    text: &MessageText,
+    parse_mode: Option<SendMessageParseMode>,
+) -> Result<RichText, Error> {
+    match (text.entities(), parse_mode) {
+        (Some(entities), _) => Ok(RichText::from_entities(text.text(), entities.to_vec())),
+        (None, Some(SendMessageParseMode::MarkdownV2)) => RichText::from_markdown_v2(text.text()),
+        (None, Some(SendMessageParseMode::HTML)) => RichText::from_html(text.text()),
+        (None, None) => Ok(RichText::new().plain(text.text())),
+    }
+}
+
+impl TextDocument {
+    /// Creates new `TextDocument` settings sending text longer than
+    /// [`MESSAGE_LENGTH_LIMIT`] as the file `file_name`, with a preview of
+    /// [`DEFAULT_PREVIEW_LENGTH`].
+    pub fn new<S>(file_name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            file_name: file_name.into(),
+            threshold: MESSAGE_LENGTH_LIMIT,
+            preview_length: DEFAULT_PREVIEW_LENGTH,
+        }
+    }
+
+    /// Sets the length above which text is sent as a file, e.g. to send
+    /// texts as files which would be split into a few messages otherwise.
+    ///
+    /// A threshold above [`MESSAGE_LENGTH_LIMIT`] is used as
+    /// [`MESSAGE_LENGTH_LIMIT`].
+    #[must_use]
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the length of the preview sent as caption.
+    #[must_use]
+    pub fn with_preview_length(mut self, preview_length: usize) -> Self {
+        self.preview_length = preview_length;
+        self
+    }
+}
+
+impl From<TextDocument> for LongText {
+    fn from(document: TextDocument) -> Self {
+        Self::Document(Box::new(document))
+    }
+}
+
+fn split_rich_text(text: &RichText, limit: usize, numbered: bool) -> Vec<RichText> {
+    let chars = utf16_chars(text.text());
+
+    // Numbers take room in every part, and splitting may need more parts
+    // than guessed, and so longer numbers.
+    let mut guess = 1;
+    loop {
+        let reserved = if numbered {
+            part_number(guess, guess).encode_utf16().count()
+        } else {
+            0
+        };
+        let ranges = split_ranges(
+            &chars,
+            text.entities(),
+            limit.saturating_sub(reserved).max(2),
+        );
+
+        let count = ranges.len();
+        if !numbered || count == 1 {
+            return ranges.into_iter().map(|range| text.slice(range)).collect();
+        }
+        if part_number(count, count).len() <= part_number(guess, guess).len() {
+            return ranges
+                .into_iter()
+                .enumerate()
+                .map(|(index, range)| text.slice(range).plain(&part_number(index + 1, count)))
+                .collect();
+        }
+        guess = count;
+    }
+}
+
+/// Returns the ranges of UTF-16 code units of the parts of `chars`.
+fn split_ranges(
+    chars: &[(usize, char)],
+    entities: &[MessageEntity],
+    limit: usize,
+) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = skip_whitespace(chars, 0);
+
+    while start < chars.len() {
+        let end = split_point(chars, entities, start, limit);
+        let (range, _) = trimmed(chars, start, end, chars.len());
+        if !range.is_empty() {
+            ranges.push(range);
+        }
+        start = skip_whitespace(chars, end);
+    }
+
+    ranges
+}
+
+/// Returns the index into `chars` where the part starting at `start` ends.
+fn split_point(
+    chars: &[(usize, char)],
+    entities: &[MessageEntity],
+    start: usize,
+    limit: usize,
+) -> usize {
+    let start_offset = chars[start].0;
+    let fits = |index: usize| offset_at(chars, index) - start_offset <= limit;
+    if fits(chars.len()) {
+        return chars.len();
+    }
+
+    // The last split point of every level, as an index into `chars`.
+    let mut points = [None; SPLIT_LEVELS.len()];
+    let mut index = start + 1;
+    while index <= chars.len() && fits(index) {
+        let boundary = boundary_before(chars, index);
+        let cuts = cuts_at(entities, offset_at(chars, index));
+        for (point, level) in points.iter_mut().zip(SPLIT_LEVELS) {
+            if boundary <= level.0 && cuts <= level.1 {
+                *point = Some(index);
+            }
+        }
+        index += 1;
+    }
+
+    // Splitting early makes many short parts, so a worse split point late
+    // in the part is preferred over a better one early in it.
+    let half = start_offset + limit / 2;
+    points
+        .iter()
+        .flatten()
+        .find(|&&index| offset_at(chars, index) >= half)
+        .or_else(|| points.iter().flatten().next())
+        .copied()
+        .unwrap_or(start + 1)
+}
+
+/// Returns the kind of boundary between `chars[index - 1]` and
+/// `chars[index]`.
+fn boundary_before(chars: &[(usize, char)], index: usize) -> Boundary {
+    let previous = chars[index - 1].1;
+    let before_previous = index.checked_sub(2).map(|index| chars[index].1);
+
+    match (before_previous, previous) {
+        (Some('\n'), '\n') => Boundary::Paragraph,
+        (_, '\n') => Boundary::Line,
+        (_, c) if c.is_whitespace() => Boundary::Word,
+        _ => Boundary::Char,
+    }
+}
+
+/// Returns which entities a split at `offset` cuts.
+fn cuts_at(entities: &[MessageEntity], offset: usize) -> Cuts {
+    entities
+        .iter()
+        .filter(|entity| entity.offset < offset && offset < entity.offset + entity.length)
+        .map(|entity| match entity.kind.as_str() {
+            "code" | "pre" | "text_link" | "text_mention" | "custom_emoji" => Cuts::Anything,
+            _ => Cuts::Formatting,
+        })
+        .max()
+        .unwrap_or(Cuts::Nothing)
+}
+
+/// Returns the UTF-16 ranges of `chars[start..end]` and `chars[end..rest_end]`
+/// without surrounding whitespace.
+fn trimmed(
+    chars: &[(usize, char)],
+    start: usize,
+    end: usize,
+    rest_end: usize,
+) -> (Range<usize>, Range<usize>) {
+    let mut part_end = end;
+    while part_end > start && chars[part_end - 1].1.is_whitespace() {
+        part_end -= 1;
+    }
+    let rest_start = skip_whitespace(chars, end);
+
+    (
+        offset_at(chars, start)..offset_at(chars, part_end),
+        offset_at(chars, rest_start)..offset_at(chars, rest_end),
+    )
+}
+
+fn skip_whitespace(chars: &[(usize, char)], mut index: usize) -> usize {
+    while index < chars.len() && chars[index].1.is_whitespace() {
+        index += 1;
+    }
+    index
+}
+
+/// Returns the characters of `text` with their offset in UTF-16 code units.
+fn utf16_chars(text: &str) -> Vec<(usize, char)> {
+    let mut offset = 0;
+    text.chars()
+        .map(|c| {
+            let char_offset = offset;
+            offset += c.len_utf16();
+            (char_offset, c)
+        })
+        .collect()
+}
+
+/// Returns the UTF-16 offset of `chars[index]`, or the length of the text if
+/// `index` is past the end.
+fn offset_at(chars: &[(usize, char)], index: usize) -> usize {
+    match chars.get(index) {
+        Some((offset, _)) => *offset,
+        None => chars
+            .last()
+            .map(|(offset, c)| offset + c.len_utf16())
+            .unwrap_or_default(),
+    }
+}
+
+fn part_number(index: usize, count: usize) -> String {
+    format!("\n({index}/{count})")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::Error,
+        long_text::{split, split_first, TextDocument},
+        rich_text::RichText,
+        types::{MessageText, SendMessageParseMode},
+    };
+
+    fn texts(parts: &[MessageText]) -> Vec<&str> {
+        parts.iter().map(MessageText::text).collect()
+    }
+
+    #[test]
+    fn text_is_split_at_the_best_boundary() {
+        let text = MessageText::from("first paragraph\n\nsecond line\nthird words here");
+        assert_eq!(split(&text, None, 100, false).unwrap(), [text.clone()][..]);
+
+        let parts = split(&text, None, 30, false).unwrap();
+        assert_eq!(
+            texts(&parts),
+            ["first paragraph", "second line\nthird words here"]
+        );
+
+        let parts = split(&text, None, 12, false).unwrap();
+        assert_eq!(
+            texts(&parts),
+            ["first", "paragraph", "second line", "third words", "here"]
+        );
+
+        let words = MessageText::from("aaaa bbbb cccc dddd eeee ffff gggg hhhh iiii jjjj kkkk");
+        let parts = split(&words, None, 12, true).unwrap();
+        assert_eq!(parts.len(), 11);
+        assert_eq!(texts(&parts)[0], "aaaa\n(1/11)");
+        assert_eq!(texts(&parts)[10], "kkkk\n(11/11)");
+    }
+
+    #[test]
+    fn whitespace_is_not_sent() {
+        let blank = MessageText::from(" \n".repeat(50));
+        assert!(matches!(
+            split(&blank, None, 10, false),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            split(&blank, None, 10, true),
+            Err(Error::InvalidInput(_))
+        ));
+
+        let sparse = MessageText::from(format!(
+            "{}one{}two{}",
+            " ".repeat(20),
+            "\n".repeat(30),
+            " ".repeat(20)
+        ));
+        let parts = split(&sparse, None, 10, false).unwrap();
+        assert_eq!(texts(&parts), ["one", "two"]);
+    }
+
+    #[test]
+    fn formatting_is_continued_and_code_is_kept_whole() {
+        let text = RichText::new()
+            .bold("bold words")
+            .plain(" then ")
+            .pre("fn main() {}", Some("rust"));
+        let parts = split(&text.into(), None, 22, true).unwrap();
+
+        assert_eq!(
+            texts(&parts),
+            ["bold words then\n(1/2)", "fn main() {}\n(2/2)"]
+        );
+        assert_eq!(
+            RichText::from_entities(parts[1].text(), parts[1].entities().unwrap().to_vec())
+                .to_markdown_v2(),
+            "```rust\nfn main() {}\n```\n\\(2/2\\)"
+        );
+
+        let html = MessageText::from("<b>one two three four</b>");
+        let parts = split(&html, Some(SendMessageParseMode::HTML), 10, false).unwrap();
+        assert_eq!(texts(&parts), ["one two", "three four"]);
+        assert!(parts
+            .iter()
+            .all(|part| part.entities().unwrap()[0].kind == "bold"));
+    }
+
+    #[test]
+    fn long_text_is_sent_as_document() {
+        let document = TextDocument::new("trace.log")
+            .with_threshold(20)
+            .with_preview_length(12);
+        let short = MessageText::from("panicked at main.rs");
+        assert_eq!(super::text_document(&short, None, &document).unwrap(), None);
+
+        let trace = MessageText::from("<b>panicked</b> at src/main.rs:10:5\nstack backtrace:");
+        let (preview, contents) =
+            super::text_document(&trace, Some(SendMessageParseMode::HTML), &document)
+                .unwrap()
+                .unwrap();
+        assert_eq!(preview.text(), "panicked…");
+        assert_eq!(preview.entities().unwrap()[0].length, 8);
+        assert_eq!(contents, "panicked at src/main.rs:10:5\nstack backtrace:");
+
+        let document = TextDocument::new("trace.log").with_threshold(10_000);
+        let long = MessageText::from("frame ".repeat(1000));
+        assert!(super::text_document(&long, None, &document)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn first_part_is_split_off() {
+        let text = MessageText::from("caption line\nrest of the text");
+        let (first, rest) = split_first(&text, None, 15).unwrap();
+
+        assert_eq!(first.text(), "caption line");
+        assert_eq!(rest.text(), "rest of the text");
+    }
+}
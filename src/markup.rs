@@ -0,0 +1,535 @@
+//! Parsers turning MarkdownV2 and HTML into text with entities, the way
+//! Telegram does, so that formatted text can be measured and split.
+
+use std::{iter::Peekable, str::Chars};
+
+use crate::{errors::Error, rich_text::RichText, types::MessageEntity};
+
+/// Text and entities of the markup parsed so far.
+#[derive(Debug, Default)]
+struct Builder {
+    text: String,
+    len: usize,
+    entities: Vec<MessageEntity>,
+}
+
+/// An entity which has been opened but not closed yet.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Open {
+    /// Markdown marker or HTML tag name which opened the entity.
+    tag: String,
+    index: usize,
+}
+
+/// Parses MarkdownV2 into text and entities.
+///
+/// Characters which must be escaped in MarkdownV2 are accepted unescaped
+/// where they cannot start an entity.
+///
+/// See <https://core.telegram.org/bots/api#markdownv2-style>
+pub(crate) fn parse_markdown_v2(markdown: &str) -> Result<RichText, Error> {
+    let mut builder = Builder::default();
+    let mut open = Vec::<Open>::new();
+    let mut chars = markdown.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        let at_line_start = line_start;
+        line_start = false;
+
+        match c {
+            '\\' => match chars.next() {
+                Some(c) => builder.push(c),
+                None => builder.push('\\'),
+            },
+            '\r' => (),
+            '\n' => {
+                let in_quote = open.last().is_some_and(|entry| is_quote_tag(&entry.tag));
+                if in_quote && chars.peek() != Some(&'>') {
+                    let entry = open.pop().expect("quote is open");
+                    builder.close(entry.index);
+                }
+                builder.push('\n');
+                line_start = true;
+            }
+            '>' if at_line_start => {
+                if !open.last().is_some_and(|entry| is_quote_tag(&entry.tag)) {
+                    push_open(&mut builder, &mut open, ">", "blockquote");
+                }
+            }
+            '*' if at_line_start && starts_with(&chars, "*>") => {
+                chars.nth(1);
+                if !open.last().is_some_and(|entry| is_quote_tag(&entry.tag)) {
+                    push_open(&mut builder, &mut open, "**>", "expandable_blockquote");
+                }
+            }
+            '|' if chars.peek() == Some(&'|') => {
+                chars.next();
+                let closes_quote = open.last().is_some_and(|entry| entry.tag == "**>")
+                    && matches!(chars.peek(), None | Some('\n'));
+                if closes_quote {
+                    let entry = open.pop().expect("quote is open");
+                    builder.close(entry.index);
+                } else {
+                    toggle(&mut builder, &mut open, "||", "spoiler")?;
+                }
+            }
+            '_' if chars.peek() == Some(&'_') => {
+                chars.next();
+                toggle(&mut builder, &mut open, "__", "underline")?;
+            }
+            '_' => toggle(&mut builder, &mut open, "_", "italic")?,
+            '*' => toggle(&mut builder, &mut open, "*", "bold")?,
+            '~' => toggle(&mut builder, &mut open, "~", "strikethrough")?,
+            '`' if starts_with(&chars, "``") => {
+                chars.nth(1);
+                parse_markdown_v2_pre(&mut builder, &mut chars)?;
+            }
+            '`' => {
+                let index = builder.open("code");
+                let code = read_until(&mut chars, '`')
+                    .ok_or_else(|| invalid_markup("code is not closed"))?;
+                builder.push_str(&code);
+                builder.close(index);
+            }
+            '!' if chars.peek() == Some(&'[') => {
+                chars.next();
+                push_open(&mut builder, &mut open, "![", "custom_emoji");
+            }
+            '[' => push_open(&mut builder, &mut open, "[", "text_link"),
+            ']' if open
+                .last()
+                .is_some_and(|entry| matches!(entry.tag.as_str(), "[" | "![")) =>
+            {
+                let entry = open.pop().expect("link is open");
+                if chars.next() != Some('(') {
+                    return Err(invalid_markup("link URL is missing"));
+                }
+                let url = read_until(&mut chars, ')')
+                    .ok_or_else(|| invalid_markup("link URL is not closed"))?;
+
+                let entity = &mut builder.entities[entry.index];
+                match entry.tag.as_str() {
+                    "![" => {
+                        let id = url
+                            .strip_prefix("tg://emoji?id=")
+                            .ok_or_else(|| invalid_markup("custom emoji URL is invalid"))?;
+                        entity.custom_emoji_id = Some(id.to_owned());
+                    }
+                    _ => entity.url = Some(url),
+                }
+                builder.close(entry.index);
+            }
+            c => builder.push(c),
+        }
+    }
+
+    while let Some(entry) = open.pop() {
+        if !is_quote_tag(&entry.tag) {
+            return Err(invalid_markup(&format!("{:?} is not closed", entry.tag)));
+        }
+        builder.close(entry.index);
+    }
+
+    Ok(builder.finish())
+}
+
+/// Parses the rest of a MarkdownV2 code block after the opening "```".
+fn parse_markdown_v2_pre(
+    builder: &mut Builder,
+    chars: &mut Peekable<Chars<'_>>,
+) -> Result<(), Error> {
+    let mut language = String::new();
+    let mut has_language_line = false;
+    let mut lookahead = chars.clone();
+    for c in lookahead.by_ref() {
+        match c {
+            '\n' => {
+                has_language_line = true;
+                break;
+            }
+            '`' | ' ' => break,
+            c => language.push(c),
+        }
+    }
+    if has_language_line {
+        *chars = lookahead;
+    } else {
+        language.clear();
+    }
+
+    let index = builder.open("pre");
+    if !language.is_empty() {
+        builder.entities[index].language = Some(language);
+    }
+
+    loop {
+        match chars.next() {
+            Some('\\') => match chars.next() {
+                Some(c) => builder.push(c),
+                None => builder.push('\\'),
+            },
+            Some('`') if starts_with(chars, "``") => {
+                chars.nth(1);
+                break;
+            }
+            Some(c) => builder.push(c),
+            None => return Err(invalid_markup("code block is not closed")),
+        }
+    }
+
+    // The line break before the closing "```" is not part of the code.
+    if builder.len > builder.entities[index].offset && builder.text.ends_with('\n') {
+        builder.text.pop();
+        builder.len -= 1;
+    }
+    builder.close(index);
+    Ok(())
+}
+
+/// Parses HTML into text and entities.
+///
+/// See <https://core.telegram.org/bots/api#html-style>
+pub(crate) fn parse_html(html: &str) -> Result<RichText, Error> {
+    let mut builder = Builder::default();
+    let mut open = Vec::<Open>::new();
+    let mut rest = html;
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '<' => {
+                let end = rest
+                    .find('>')
+                    .ok_or_else(|| invalid_markup("tag is not closed"))?;
+                parse_html_tag(&mut builder, &mut open, &rest[1..end])?;
+                rest = &rest[end + 1..];
+            }
+            '&' => {
+                let (c, len) = parse_html_entity(rest);
+                builder.push(c);
+                rest = &rest[len..];
+            }
+            c => {
+                builder.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    match open.last() {
+        Some(entry) => Err(invalid_markup(&format!("<{}> is not closed", entry.tag))),
+        None => Ok(builder.finish()),
+    }
+}
+
+fn parse_html_tag(builder: &mut Builder, open: &mut Vec<Open>, tag: &str) -> Result<(), Error> {
+    if let Some(name) = tag.strip_prefix('/') {
+        let name = name.trim().to_ascii_lowercase();
+        let entry = open
+            .pop()
+            .filter(|entry| entry.tag == name)
+            .ok_or_else(|| invalid_markup(&format!("unexpected </{name}>")))?;
+        // `<pre><code class="language-x">` is one entity, see below.
+        if entry.index != usize::MAX {
+            builder.close(entry.index);
+        }
+        return Ok(());
+    }
+
+    let (name, attributes) = match tag.split_once(char::is_whitespace) {
+        Some((name, attributes)) => (name.to_ascii_lowercase(), attributes),
+        None => (tag.to_ascii_lowercase(), ""),
+    };
+    let attributes = parse_html_attributes(attributes);
+    let attribute = |key: &str| {
+        attributes
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.clone())
+    };
+
+    let kind = match name.as_str() {
+        "b" | "strong" => "bold",
+        "i" | "em" => "italic",
+        "u" | "ins" => "underline",
+        "s" | "strike" | "del" => "strikethrough",
+        "tg-spoiler" => "spoiler",
+        "span" if attribute("class").as_deref() == Some("tg-spoiler") => "spoiler",
+        "a" => "text_link",
+        "code" => "code",
+        "pre" => "pre",
+        "tg-emoji" => "custom_emoji",
+        "blockquote" if attribute("expandable").is_some() => "expandable_blockquote",
+        "blockquote" => "blockquote",
+        _ => return Err(invalid_markup(&format!("unsupported tag <{name}>"))),
+    };
+
+    // The language of a code block is set on its inner `<code>` tag.
+    let in_pre = open.last().and_then(|entry| {
+        let entity = builder.entities.get(entry.index)?;
+        (entry.tag == "pre" && entity.offset == builder.len).then_some(entry.index)
+    });
+    if let (Some(pre), "code") = (in_pre, kind) {
+        let language = attribute("class")
+            .as_deref()
+            .and_then(|class| class.strip_prefix("language-"))
+            .map(str::to_owned);
+        builder.entities[pre].language = language;
+        open.push(Open {
+            tag: name,
+            index: usize::MAX,
+        });
+        return Ok(());
+    }
+
+    let index = builder.open(kind);
+    let entity = &mut builder.entities[index];
+    entity.url = match kind {
+        "text_link" => Some(attribute("href").unwrap_or_default()),
+        _ => None,
+    };
+    entity.custom_emoji_id = match kind {
+        "custom_emoji" => Some(attribute("emoji-id").unwrap_or_default()),
+        _ => None,
+    };
+    open.push(Open { tag: name, index });
+    Ok(())
+}
+
+/// Parses attributes like `href="..."` or `expandable` of an HTML tag.
+fn parse_html_attributes(mut attributes: &str) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+
+    loop {
+        attributes = attributes.trim_start();
+        let name_len = attributes
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(attributes.len());
+        if name_len == 0 {
+            break;
+        }
+        let name = attributes[..name_len].to_ascii_lowercase();
+        attributes = attributes[name_len..].trim_start();
+
+        let value = match attributes.strip_prefix('=') {
+            Some(rest) => {
+                let rest = rest.trim_start();
+                let (value, rest) = match rest.chars().next() {
+                    Some(quote @ ('"' | '\'')) => match rest[1..].split_once(quote) {
+                        Some((value, rest)) => (value, rest),
+                        None => (&rest[1..], ""),
+                    },
+                    _ => rest.split_once(char::is_whitespace).unwrap_or((rest, "")),
+                };
+                attributes = rest;
+                decode_html(value)
+            }
+            None => String::new(),
+        };
+        parsed.push((name, value));
+    }
+
+    parsed
+}
+
+/// Decodes the HTML entity at the start of `text`, and returns the character
+/// with the length of the entity. A lone `&` stands for itself.
+fn parse_html_entity(text: &str) -> (char, usize) {
+    let entity = text
+        .get(1..)
+        .and_then(|rest| rest.split_once(';'))
+        .map(|(entity, _)| entity)
+        .filter(|entity| entity.len() <= 10);
+
+    let c = match entity {
+        Some("lt") => Some('<'),
+        Some("gt") => Some('>'),
+        Some("amp") => Some('&'),
+        Some("quot") => Some('"'),
+        Some(entity) => entity
+            .strip_prefix("#x")
+            .or_else(|| entity.strip_prefix("#X"))
+            .map(|hex| u32::from_str_radix(hex, 16))
+            .or_else(|| entity.strip_prefix('#').map(str::parse))
+            .and_then(Result::ok)
+            .and_then(char::from_u32),
+        None => None,
+    };
+
+    match (c, entity) {
+        (Some(c), Some(entity)) => (c, entity.len() + 2),
+        _ => ('&', 1),
+    }
+}
+
+fn decode_html(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let len = match c {
+            '&' => {
+                let (c, len) = parse_html_entity(rest);
+                decoded.push(c);
+                len
+            }
+            c => {
+                decoded.push(c);
+                c.len_utf8()
+            }
+        };
+        rest = &rest[len..];
+    }
+
+    decoded
+}
+
+impl Builder {
+    fn push(&mut self, c: char) {
+        self.text.push(c);
+        self.len += c.len_utf16();
+    }
+
+    fn push_str(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.len += text.encode_utf16().count();
+    }
+
+    /// Starts an entity of type `kind` at the end of the text, and returns
+    /// its index.
+    fn open(&mut self, kind: &str) -> usize {
+        self.entities.push(MessageEntity {
+            kind: kind.to_owned(),
+            offset: self.len,
+            length: 0,
+            url: None,
+            user: None,
+            language: None,
+            custom_emoji_id: None,
+        });
+        self.entities.len() - 1
+    }
+
+    /// Ends the entity at `index` at the end of the text.
+    fn close(&mut self, index: usize) {
+        let entity = &mut self.entities[index];
+        entity.length = self.len - entity.offset;
+    }
+
+    fn finish(self) -> RichText {
+        let entities = self
+            .entities
+            .into_iter()
+            .filter(|entity| entity.length > 0)
+            .collect();
+        RichText::from_entities(self.text, entities)
+    }
+}
+
+fn push_open(builder: &mut Builder, open: &mut Vec<Open>, tag: &str, kind: &str) {
+    let index = builder.open(kind);
+    open.push(Open {
+        tag: tag.to_owned(),
+        index,
+    });
+}
+
+/// Opens an entity on a marker, or closes it if it is the innermost open
+/// entity.
+fn toggle(builder: &mut Builder, open: &mut Vec<Open>, tag: &str, kind: &str) -> Result<(), Error> {
+    match open.iter().rposition(|entry| entry.tag == tag) {
+        Some(position) if position == open.len() - 1 => {
+            let entry = open.pop().expect("entity is open");
+            builder.close(entry.index);
+            Ok(())
+        }
+        Some(_) => Err(invalid_markup(&format!(
+            "{tag:?} closes overlapping entities"
+        ))),
+        None => {
+            push_open(builder, open, tag, kind);
+            Ok(())
+        }
+    }
+}
+
+/// Reads up to the next unescaped `end`, in which only `end` and `\` can be
+/// escaped, and returns `None` if there is none.
+fn read_until(chars: &mut Peekable<Chars<'_>>, end: char) -> Option<String> {
+    let mut read = String::new();
+    loop {
+        match chars.next()? {
+            '\\' => read.push(chars.next()?),
+            c if c == end => return Some(read),
+            c => read.push(c),
+        }
+    }
+}
+
+fn starts_with(chars: &Peekable<Chars<'_>>, prefix: &str) -> bool {
+    chars
+        .clone()
+        .take(prefix.chars().count())
+        .eq(prefix.chars())
+}
+
+fn is_quote_tag(tag: &str) -> bool {
+    matches!(tag, ">" | "**>")
+}
+
+fn invalid_markup(reason: &str) -> Error {
+    Error::InvalidInput(format!("can't parse entities: {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        markup::{parse_html, parse_markdown_v2},
+        rich_text::RichText,
+    };
+
+    #[test]
+    fn markdown_v2_is_parsed() {
+        let text = RichText::new()
+            .bold("BTC-PERP")
+            .plain(" 1.5% ")
+            .underline(RichText::new().italic("up"))
+            .plain(" ")
+            .link("chart", "https://example.com/a_(b)")
+            .blockquote("price band\nhit")
+            .pre("let x = `a`;", Some("rust"))
+            .expandable_blockquote("details")
+            .spoiler("secret");
+
+        assert_eq!(parse_markdown_v2(&text.to_markdown_v2()).unwrap(), text);
+        assert_eq!(
+            parse_markdown_v2("1.5% - done!").unwrap(),
+            RichText::new().plain("1.5% - done!")
+        );
+        assert!(parse_markdown_v2("*bold _italic* oops_").is_err());
+        assert!(parse_markdown_v2("`code").is_err());
+    }
+
+    #[test]
+    fn html_is_parsed() {
+        let text = RichText::new()
+            .bold("P&L")
+            .plain(" <1% ")
+            .link(
+                RichText::new().italic("chart"),
+                "https://example.com/?a=1&b=\"2\"",
+            )
+            .blockquote("quote")
+            .pre("fn main() {}", Some("rust"))
+            .pre("plain", None)
+            .custom_emoji("👍", "5368324170671202286");
+
+        assert_eq!(parse_html(&text.to_html()).unwrap(), text);
+        assert_eq!(
+            parse_html("<strong>a</strong> &#x41;&#66; &amp; &nbsp;").unwrap(),
+            RichText::new().bold("a").plain(" AB & &nbsp;")
+        );
+        assert!(parse_html("<b>bold").is_err());
+        assert!(parse_html("<b><i>x</b></i>").is_err());
+        assert!(parse_html("<marquee>x</marquee>").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{
+    errors::Error,
+    types::{ChatId, ResponseParameters},
+};
+
+/// Function called once a chat has been migrated, with the old chat and the
+/// id of the supergroup it was upgraded to.
+pub(crate) type MigrationHook = Arc<dyn Fn(&ChatId, i64) + Send + Sync>;
+
+/// Chats which have been upgraded to supergroups, shared by all clones of a
+/// [`Bot`], so that requests to the old chat are sent to the new one.
+///
+/// [`Bot`]: crate::bot::Bot
+#[derive(Default)]
+pub(crate) struct Migrations {
+    chats: Mutex<HashMap<String, i64>>,
+    hook: Mutex<Option<MigrationHook>>,
+}
+
+impl Migrations {
+    /// Calls `hook` on every new migration, instead of the previous hook.
+    pub(crate) fn set_hook(&self, hook: MigrationHook) {
+        *self.hook.lock().expect("chat migration hook is poisoned") = Some(hook);
+    }
+
+    /// Returns the chat requests to `chat_id` are sent to.
+    pub(crate) fn resolve(&self, chat_id: &str) -> String {
+        match self.get(chat_id) {
+            Some(to) => to.to_string(),
+            None => chat_id.to_owned(),
+        }
+    }
+
+    /// Returns the id of the supergroup `chat_id` has been upgraded to, if
+    /// any.
+    pub(crate) fn get(&self, chat_id: &str) -> Option<i64> {
+        self.lock().get(chat_id).copied()
+    }
+
+    /// Records the migration of `chat_id` if `err` reports one, and returns
+    /// the chat to send the request to instead.
+    pub(crate) fn follow(&self, chat_id: &str, err: &Error) -> Option<String> {
+        let to = migrate_to_chat_id(err)?;
+        self.lock().insert(chat_id.to_owned(), to);
+
+        // The hook is called without holding the lock, it may set another
+        // hook.
+        let hook = self
+            .hook
+            .lock()
+            .expect("chat migration hook is poisoned")
+            .clone();
+        if let Some(hook) = hook {
+            hook(&ChatId::from(chat_id), to);
+        }
+
+        Some(to.to_string())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, i64>> {
+        self.chats.lock().expect("chat migrations are poisoned")
+    }
+}
+
+impl fmt::Debug for Migrations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrations")
+            .field("chats", &self.chats)
+            .field(
+                "hook",
+                &self
+                    .hook
+                    .lock()
+                    .expect("chat migration hook is poisoned")
+                    .is_some(),
+            )
+            .finish()
+    }
+}
+
+/// Returns the id of the supergroup the chat of a failed request has been
+/// upgraded to, if that is why the request failed.
+pub(crate) fn migrate_to_chat_id(err: &Error) -> Option<i64> {
+    match err {
+        Error::Api {
+            parameters:
+                Some(ResponseParameters {
+                    migrate_to_chat_id: Some(to),
+                    ..
+                }),
+            ..
+        } => Some(*to),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        errors::Error,
+        migration::Migrations,
+        types::{ChatId, ResponseParameters},
+    };
+
+    #[test]
+    fn migrations_are_followed_and_reported() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let hook = {
+            let reported = Arc::clone(&reported);
+            Arc::new(move |from: &ChatId, to: i64| {
+                reported.lock().unwrap().push((from.clone(), to))
+            })
+        };
+        let migrations = Migrations::default();
+        migrations.set_hook(hook);
+
+        let other = Error::Api {
+            error_code: 400,
+            description: "Bad Request: message text is empty".to_owned(),
+            parameters: None,
+        };
+        assert_eq!(migrations.follow("-123", &other), None);
+        assert_eq!(migrations.resolve("-123"), "-123");
+
+        let migrated = Error::Api {
+            error_code: 400,
+            description: "Bad Request: group chat was upgraded to a supergroup chat".to_owned(),
+            parameters: Some(ResponseParameters {
+                migrate_to_chat_id: Some(-1001234),
+                retry_after: None,
+            }),
+        };
+        assert_eq!(
+            migrations.follow("-123", &migrated),
+            Some("-1001234".to_owned())
+        );
+        assert_eq!(migrations.resolve("-123"), "-1001234");
+        assert_eq!(migrations.resolve("-456"), "-456");
+        assert_eq!(*reported.lock().unwrap(), [(ChatId::Id(-123), -1001234)]);
+    }
+}
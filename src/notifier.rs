@@ -0,0 +1,532 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use tokio::sync::Notify;
+
+use crate::{
+    bot::Bot,
+    requester::Requester,
+    types::{ChatId, SendMessageOption},
+};
+
+/// A fire-and-forget handle for sending messages in the background.
+///
+/// Messages are put into a bounded in-memory queue and sent one by one, in
+/// order, by a tokio task which owns the [`Bot`]. Enqueuing never waits for
+/// Telegram, so it is cheap enough to be called from latency sensitive code.
+///
+/// Errors of the background sends are not reported to the caller, configure
+/// [`Bot::with_retry_policy`] to make delivery more reliable. Use
+/// [`Notifier::flush`] before shutting down to make sure that all queued
+/// messages are sent.
+///
+/// `Notifier` is cheap to clone, all clones share the same queue. The
+/// background task finishes sending the queued messages and exits once every
+/// clone has been dropped.
+///
+/// [`Bot::with_retry_policy`]: crate::bot::Bot::with_retry_policy
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    handle: Arc<Handle>,
+}
+
+/// What to do with a message enqueued into a full [`Notifier`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OverflowPolicy {
+    /// Wait until there is room in the queue. [`Notifier::notify`] rejects the
+    /// message instead, since it cannot wait.
+    #[default]
+    Block,
+
+    /// Drop the message being enqueued.
+    DropNewest,
+
+    /// Drop the oldest message in the queue to make room.
+    DropOldest,
+}
+
+/// A message to be sent by a [`Notifier`].
+#[derive(Debug)]
+pub struct Notification {
+    /// Text of the message.
+    pub text: String,
+
+    /// Options to send the message with.
+    pub options: Option<SendMessageOption>,
+
+    /// Chat to send the message to, the chat of the bot if `None`.
+    pub chat_id: Option<ChatId>,
+}
+
+/// An error returned when a [`Notification`] could not be enqueued. The
+/// rejected notification is handed back.
+#[derive(Debug)]
+pub enum QueueError {
+    /// The queue is full.
+    Full(Box<Notification>),
+
+    /// The background task is gone, e.g. the runtime has shut down.
+    Closed(Box<Notification>),
+}
+
+/// Ends the background task once the last `Notifier` clone is dropped.
+#[derive(Debug)]
+struct Handle {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    capacity: usize,
+    overflow: OverflowPolicy,
+    state: Mutex<State>,
+
+    /// Signaled when a message is enqueued or the queue is closed.
+    enqueued: Notify,
+
+    /// Signaled when a message is taken out of the queue.
+    dequeued: Notify,
+
+    /// Signaled when messages have been sent or dropped, or the background
+    /// task has stopped.
+    completed: Notify,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    queue: VecDeque<Notification>,
+
+    /// Number of messages enqueued so far.
+    pushed: u64,
+
+    /// Number of messages sent or dropped so far. Messages are completed in
+    /// the order they were enqueued.
+    completed: u64,
+    closed: bool,
+
+    /// Whether the background task has stopped, also if it panicked.
+    stopped: bool,
+    dropped: u64,
+    failed: u64,
+}
+
+/// Completes the message being sent once dropped, also if sending panicked.
+struct Sending<'a> {
+    shared: &'a Shared,
+    delivered: bool,
+}
+
+/// Stops the queue once dropped, also if the background task panicked.
+struct Stopping<'a>(&'a Shared);
+
+impl Notifier {
+    /// Creates a new `Notifier` sending messages through `bot`, holding up to
+    /// `capacity` messages in its queue.
+    ///
+    /// # Panics
+    /// If called outside of a tokio runtime, or if `capacity` is `0`.
+    pub fn new(bot: Bot, capacity: usize, overflow: OverflowPolicy) -> Self {
+        Self::spawn(bot, capacity, overflow)
+    }
+
+    fn spawn<B>(bot: B, capacity: usize, overflow: OverflowPolicy) -> Self
+    where
+        B: Requester,
+    {
+        assert!(capacity > 0, "Notifier capacity must be positive");
+
+        let shared = Arc::new(Shared {
+            capacity,
+            overflow,
+            state: Mutex::new(State::default()),
+            enqueued: Notify::new(),
+            dequeued: Notify::new(),
+            completed: Notify::new(),
+        });
+
+        tokio::spawn(run(bot, Arc::clone(&shared)));
+
+        Self {
+            handle: Arc::new(Handle { shared }),
+        }
+    }
+
+    /// Enqueues a message without waiting.
+    ///
+    /// If the queue is full, the message is handled according to the
+    /// [`OverflowPolicy`], except that [`OverflowPolicy::Block`] rejects it.
+    pub fn notify<N>(&self, notification: N) -> Result<(), QueueError>
+    where
+        N: Into<Notification>,
+    {
+        self.handle.shared.push(notification.into())
+    }
+
+    /// Enqueues a message, waiting for room in the queue if the
+    /// [`OverflowPolicy`] is [`OverflowPolicy::Block`].
+    pub async fn notify_wait<N>(&self, notification: N) -> Result<(), QueueError>
+    where
+        N: Into<Notification>,
+    {
+        let shared = &self.handle.shared;
+        let mut notification = notification.into();
+
+        loop {
+            let dequeued = shared.dequeued.notified();
+            tokio::pin!(dequeued);
+            dequeued.as_mut().enable();
+
+            match shared.push(notification) {
+                Err(QueueError::Full(rejected)) if shared.overflow == OverflowPolicy::Block => {
+                    notification = *rejected;
+                    dequeued.await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Waits until every message enqueued so far has been sent or dropped.
+    ///
+    /// Messages enqueued while waiting are not waited for.
+    pub async fn flush(&self) {
+        let shared = &self.handle.shared;
+        let pushed = shared.lock().pushed;
+
+        loop {
+            let completed = shared.completed.notified();
+            tokio::pin!(completed);
+            completed.as_mut().enable();
+
+            {
+                let state = shared.lock();
+                if state.completed >= pushed || state.stopped {
+                    return;
+                }
+            }
+
+            completed.await;
+        }
+    }
+
+    /// Returns the number of messages waiting in the queue.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.handle.shared.lock().queue.len()
+    }
+
+    /// Returns whether no messages are waiting in the queue.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of messages the queue can hold.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.handle.shared.capacity
+    }
+
+    /// Returns the number of messages dropped because the queue was full.
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.handle.shared.lock().dropped
+    }
+
+    /// Returns the number of messages Telegram failed to deliver.
+    #[must_use]
+    pub fn failed(&self) -> u64 {
+        self.handle.shared.lock().failed
+    }
+}
+
+impl Notification {
+    /// Creates a new `Notification` sent to `chat_id` instead of the chat of
+    /// the bot.
+    pub fn to<C, T>(chat_id: C, text: T) -> Self
+    where
+        C: Into<ChatId>,
+        T: Into<String>,
+    {
+        Self {
+            text: text.into(),
+            options: None,
+            chat_id: Some(chat_id.into()),
+        }
+    }
+}
+
+impl From<&str> for Notification {
+    fn from(text: &str) -> Self {
+        text.to_owned().into()
+    }
+}
+
+impl From<String> for Notification {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            options: None,
+            chat_id: None,
+        }
+    }
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => write!(f, "notification queue is full"),
+            Self::Closed(_) => write!(f, "notification queue is closed"),
+        }
+    }
+}
+
+impl std::error::Error for QueueError {}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.enqueued.notify_one();
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("notifier state is poisoned")
+    }
+
+    fn push(&self, notification: Notification) -> Result<(), QueueError> {
+        let mut state = self.lock();
+        if state.closed {
+            return Err(QueueError::Closed(Box::new(notification)));
+        }
+
+        if state.queue.len() >= self.capacity {
+            match self.overflow {
+                OverflowPolicy::Block => return Err(QueueError::Full(Box::new(notification))),
+                OverflowPolicy::DropNewest => {
+                    state.dropped += 1;
+                    return Err(QueueError::Full(Box::new(notification)));
+                }
+                OverflowPolicy::DropOldest => {
+                    state.queue.pop_front();
+                    state.dropped += 1;
+                    state.completed += 1;
+                    self.completed.notify_waiters();
+                }
+            }
+        }
+
+        state.queue.push_back(notification);
+        state.pushed += 1;
+        drop(state);
+
+        self.enqueued.notify_one();
+        Ok(())
+    }
+
+    /// Takes the next message out of the queue, or returns `None` once the
+    /// queue is closed and drained.
+    async fn pop(&self) -> Option<Notification> {
+        loop {
+            {
+                let mut state = self.lock();
+                match state.queue.pop_front() {
+                    Some(notification) => {
+                        drop(state);
+                        self.dequeued.notify_one();
+                        return Some(notification);
+                    }
+                    None if state.closed => return None,
+                    None => (),
+                }
+            }
+
+            self.enqueued.notified().await;
+        }
+    }
+
+    fn sent(&self, delivered: bool) {
+        let mut state = self.lock();
+        state.completed += 1;
+        if !delivered {
+            state.failed += 1;
+        }
+        drop(state);
+
+        self.completed.notify_waiters();
+    }
+}
+
+impl Drop for Sending<'_> {
+    fn drop(&mut self) {
+        self.shared.sent(self.delivered);
+    }
+}
+
+impl Drop for Stopping<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.closed = true;
+        state.stopped = true;
+        drop(state);
+
+        self.0.completed.notify_waiters();
+    }
+}
+
+/// Sends queued messages until the queue is closed and drained.
+async fn run<B>(bot: B, shared: Arc<Shared>)
+where
+    B: Requester,
+{
+    let _stopping = Stopping(&shared);
+
+    while let Some(notification) = shared.pop().await {
+        let mut sending = Sending {
+            shared: &shared,
+            delivered: false,
+        };
+
+        let bot = match notification.chat_id {
+            Some(chat_id) => bot.for_chat(chat_id),
+            None => bot.clone(),
+        };
+        let result = bot
+            .send_message(&notification.text, notification.options)
+            .await;
+        sending.delivered = result.is_ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Mutex, time::Duration};
+
+    use tokio::{sync::Notify, time::Instant};
+
+    use crate::{
+        notifier::{Notifier, OverflowPolicy, QueueError, Sending, Shared, State, Stopping},
+        requester::mock::{MockBot, Request},
+    };
+
+    fn shared(overflow: OverflowPolicy) -> Shared {
+        Shared {
+            capacity: 2,
+            overflow,
+            state: Mutex::new(State::default()),
+            enqueued: Notify::new(),
+            dequeued: Notify::new(),
+            completed: Notify::new(),
+        }
+    }
+
+    fn texts(shared: &Shared) -> Vec<String> {
+        shared
+            .lock()
+            .queue
+            .iter()
+            .map(|notification| notification.text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn block_rejects_when_full() {
+        let shared = shared(OverflowPolicy::Block);
+        shared.push("1".into()).unwrap();
+        shared.push("2".into()).unwrap();
+
+        assert!(matches!(
+            shared.push("3".into()),
+            Err(QueueError::Full(notification)) if notification.text == "3"
+        ));
+        assert_eq!(texts(&shared), ["1", "2"]);
+        assert_eq!(shared.lock().dropped, 0);
+    }
+
+    #[test]
+    fn drop_newest_drops_enqueued_message() {
+        let shared = shared(OverflowPolicy::DropNewest);
+        shared.push("1".into()).unwrap();
+        shared.push("2".into()).unwrap();
+
+        assert!(shared.push("3".into()).is_err());
+        assert_eq!(texts(&shared), ["1", "2"]);
+        assert_eq!(shared.lock().dropped, 1);
+    }
+
+    #[test]
+    fn drop_oldest_makes_room() {
+        let shared = shared(OverflowPolicy::DropOldest);
+        shared.push("1".into()).unwrap();
+        shared.push("2".into()).unwrap();
+
+        assert!(shared.push("3".into()).is_ok());
+        assert_eq!(texts(&shared), ["2", "3"]);
+        assert_eq!(shared.lock().dropped, 1);
+    }
+
+    #[tokio::test]
+    async fn pop_drains_closed_queue() {
+        let shared = shared(OverflowPolicy::Block);
+        shared.push("1".into()).unwrap();
+        shared.lock().closed = true;
+
+        assert!(matches!(
+            shared.push("2".into()),
+            Err(QueueError::Closed(_))
+        ));
+        assert_eq!(shared.pop().await.unwrap().text, "1");
+        shared.sent(true);
+        assert!(shared.pop().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn guards_complete_messages_when_dropped() {
+        let shared = shared(OverflowPolicy::Block);
+        shared.push("1".into()).unwrap();
+        shared.push("2".into()).unwrap();
+
+        // As if sending panicked.
+        shared.pop().await.unwrap();
+        drop(Sending {
+            shared: &shared,
+            delivered: false,
+        });
+        assert_eq!(shared.lock().completed, 1);
+        assert_eq!(shared.lock().failed, 1);
+
+        drop(Stopping(&shared));
+        assert!(shared.lock().stopped);
+        assert!(matches!(
+            shared.push("3".into()),
+            Err(QueueError::Closed(_))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flush_waits_for_messages_enqueued_before() {
+        let bot = MockBot::new("-100").with_latency(Duration::from_secs(1));
+        let notifier = Notifier::spawn(bot.clone(), 8, OverflowPolicy::Block);
+        notifier.notify("1").unwrap();
+        notifier.notify("2").unwrap();
+
+        let start = Instant::now();
+        tokio::join!(notifier.flush(), async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            notifier.notify("3").unwrap();
+        });
+
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+        assert_eq!(bot.take_requests().len(), 2);
+
+        notifier.flush().await;
+        match &bot.take_requests()[..] {
+            [Request::SendMessage { text, .. }] => assert_eq!(text, "3"),
+            requests => panic!("unexpected requests: {requests:?}"),
+        }
+    }
+}
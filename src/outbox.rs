@@ -0,0 +1,316 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{
+    bot::Bot,
+    errors::Error,
+    retry::ErrorClass,
+    types::{MessageText, RequestObj, SendMessageOption},
+};
+
+/// A durable outbox which keeps messages on disk until Telegram has
+/// acknowledged them.
+///
+/// Every message is appended to a log file before it is sent, and marked as
+/// delivered once Telegram accepted it. Messages left undelivered, e.g.
+/// because the process crashed, are sent again in their original order by
+/// [`Outbox::replay`], which should be called on startup.
+///
+/// Delivery is at-least-once: a message sent right before a crash, but not yet
+/// marked as delivered, is sent again on replay.
+///
+/// A message which Telegram rejects for good (e.g. because of malformed
+/// markup) is discarded and logged rather than blocking the messages after
+/// it.
+#[derive(Debug)]
+pub struct Outbox {
+    log: Mutex<Log>,
+
+    /// The log file, written on a blocking thread while holding `sending`.
+    file: Arc<File>,
+
+    /// Serializes deliveries and writes to the log file, so that messages are
+    /// sent and recorded in order.
+    sending: tokio::sync::Mutex<()>,
+}
+
+/// An entry of the log file.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Record {
+    Enqueued { id: u64, request: RequestObj },
+    Delivered { id: u64 },
+    Discarded { id: u64 },
+}
+
+/// The messages of the log file not yet delivered.
+#[derive(Debug)]
+struct Log {
+    next_id: u64,
+    pending: BTreeMap<u64, RequestObj>,
+}
+
+impl Outbox {
+    /// Opens the outbox log at `path`, creating it if it does not exist.
+    ///
+    /// The log is compacted, so that it only holds undelivered messages.
+    pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let log = Log::read(path)?;
+        let file = compact(path, &log.pending)?;
+
+        Ok(Self {
+            log: Mutex::new(log),
+            file: Arc::new(file),
+            sending: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Records a message and sends it through `bot`, after any messages still
+    /// pending in the outbox.
+    ///
+    /// Returns the error of Telegram if it rejected this message, which is
+    /// then discarded. Earlier messages rejected by Telegram are discarded and
+    /// logged. If this or an earlier message failed with a transient error,
+    /// this message stays in the outbox and is sent again on the next call or
+    /// [`Outbox::replay`], and the error is returned.
+    pub async fn send_message<T>(
+        &self,
+        bot: &Bot,
+        msg: T,
+        options: Option<SendMessageOption>,
+    ) -> Result<(), Error>
+    where
+        T: Into<MessageText>,
+    {
+        let msg = msg.into();
+        if msg.is_empty() {
+            return Err(Error::InvalidInput(
+                "message text must not be empty".to_owned(),
+            ));
+        }
+
+        let request = bot.build_request_obj(&msg, options);
+
+        let _sending = self.sending.lock().await;
+        let id = self.append(request).await?;
+        self.deliver_pending(bot, Some(id)).await
+    }
+
+    /// Sends all undelivered messages through `bot`, in order.
+    ///
+    /// Messages rejected by Telegram are discarded and logged. Returns the
+    /// error which stopped the replay, if a message failed with a transient
+    /// error.
+    pub async fn replay(&self, bot: &Bot) -> Result<(), Error> {
+        let _sending = self.sending.lock().await;
+        self.deliver_pending(bot, None).await
+    }
+
+    /// Returns the number of undelivered messages.
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.lock().pending.len()
+    }
+
+    /// Sends the pending messages in order, and returns the error of the
+    /// message with the id `sent` if Telegram rejected it, or the transient
+    /// error which stopped the delivery.
+    async fn deliver_pending(&self, bot: &Bot, sent: Option<u64>) -> Result<(), Error> {
+        let mut result = Ok(());
+
+        loop {
+            let next = self
+                .lock()
+                .pending
+                .first_key_value()
+                .map(|(id, request)| (*id, request.clone()));
+
+            let (id, request) = match next {
+                Some(next) => next,
+                None => return result,
+            };
+
+            match bot.send_request_obj(&request).await {
+                Ok(_) => self.complete(Record::Delivered { id }).await?,
+                Err(err) if ErrorClass::of(&err).is_none() => {
+                    self.complete(Record::Discarded { id }).await?;
+                    if sent == Some(id) {
+                        result = Err(err);
+                    } else {
+                        log::warn!("discarded outbox message {id} rejected by Telegram: {err}");
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Records a message as pending, and returns its id.
+    ///
+    /// Must be called while holding `sending`, like [`Outbox::complete`].
+    async fn append(&self, request: RequestObj) -> Result<u64, Error> {
+        let id = self.lock().next_id;
+        self.write(&Record::Enqueued {
+            id,
+            request: request.clone(),
+        })
+        .await?;
+
+        let mut log = self.lock();
+        log.next_id += 1;
+        log.pending.insert(id, request);
+        Ok(id)
+    }
+
+    /// Records a message as delivered or discarded.
+    async fn complete(&self, record: Record) -> Result<(), Error> {
+        self.write(&record).await?;
+        match record {
+            Record::Delivered { id } | Record::Discarded { id } => {
+                self.lock().pending.remove(&id);
+            }
+            Record::Enqueued { .. } => (),
+        }
+        Ok(())
+    }
+
+    /// Appends `record` to the log file, and waits until it is on disk.
+    async fn write(&self, record: &Record) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        let file = Arc::clone(&self.file);
+        tokio::task::spawn_blocking(move || -> io::Result<()> {
+            (&*file).write_all(&line)?;
+            file.sync_data()
+        })
+        .await
+        .expect("outbox log writer panicked")?;
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Log> {
+        self.log.lock().expect("outbox log is poisoned")
+    }
+}
+
+impl Log {
+    /// Reads the messages of the log file at `path` not yet delivered.
+    fn read(path: &Path) -> Result<Self, Error> {
+        let mut pending = BTreeMap::new();
+        let mut next_id = 0;
+
+        if path.exists() {
+            let lines = BufReader::new(File::open(path)?)
+                .lines()
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for (i, line) in lines.iter().enumerate() {
+                let record = match serde_json::from_str::<Record>(line) {
+                    Ok(record) => record,
+                    // The last line may be cut short by a crash while writing.
+                    Err(_) if i + 1 == lines.len() => break,
+                    Err(err) => return Err(err.into()),
+                };
+
+                match record {
+                    Record::Enqueued { id, request } => {
+                        next_id = next_id.max(id + 1);
+                        pending.insert(id, request);
+                    }
+                    Record::Delivered { id } | Record::Discarded { id } => {
+                        pending.remove(&id);
+                    }
+                }
+            }
+        }
+
+        Ok(Self { next_id, pending })
+    }
+}
+
+/// Rewrites the log at `path` so that it only holds `pending` messages, and
+/// returns it opened for appending.
+fn compact(path: &Path, pending: &BTreeMap<u64, RequestObj>) -> Result<File, Error> {
+    let mut tmp_path = PathBuf::from(path);
+    tmp_path.set_extension("tmp");
+
+    {
+        let mut tmp = File::create(&tmp_path)?;
+        for (id, request) in pending {
+            let mut line = serde_json::to_vec(&Record::Enqueued {
+                id: *id,
+                request: request.clone(),
+            })?;
+            line.push(b'\n');
+            tmp.write_all(&line)?;
+        }
+        tmp.sync_all()?;
+    }
+
+    fs::rename(&tmp_path, path)?;
+
+    Ok(OpenOptions::new().append(true).open(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write};
+
+    use tempfile::tempdir;
+
+    use crate::{
+        outbox::{Outbox, Record},
+        types::RequestObj,
+    };
+
+    fn request(text: &str) -> RequestObj {
+        RequestObj::new("-100", text, None)
+    }
+
+    #[tokio::test]
+    async fn undelivered_messages_survive_reopening() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("outbox.log");
+
+        let outbox = Outbox::open(&path).unwrap();
+        outbox.append(request("1")).await.unwrap();
+        outbox.append(request("2")).await.unwrap();
+        outbox.append(request("3")).await.unwrap();
+        outbox.complete(Record::Delivered { id: 0 }).await.unwrap();
+        outbox.complete(Record::Discarded { id: 2 }).await.unwrap();
+        drop(outbox);
+
+        let outbox = Outbox::open(&path).unwrap();
+        let log = outbox.lock();
+        assert_eq!(log.pending.len(), 1);
+        assert_eq!(log.pending[&1], request("2"));
+        assert_eq!(log.next_id, 3);
+
+        // Compaction leaves only the pending message in the file.
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn truncated_last_line_is_ignored() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("outbox.log");
+
+        let outbox = Outbox::open(&path).unwrap();
+        outbox.append(request("1")).await.unwrap();
+        (&*outbox.file).write_all(br#"{"op":"delivered","#).unwrap();
+        drop(outbox);
+
+        let outbox = Outbox::open(&path).unwrap();
+        assert_eq!(outbox.pending(), 1);
+    }
+}
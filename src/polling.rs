@@ -0,0 +1,295 @@
+use std::{
+    collections::VecDeque,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::stream::{self, Stream};
+
+use crate::{bot::Bot, errors::Error, retry::ErrorClass, types::Update};
+
+/// Default long polling timeout.
+pub const DEFAULT_POLLING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before polling again after a transient error.
+const ERROR_DELAY: Duration = Duration::from_secs(1);
+
+/// A [`Stream`] of incoming updates, received using long polling.
+///
+/// Each update is yielded once, in order. An update counts as consumed once
+/// the stream is polled for the next one. Telegram is told about consumed
+/// updates with the next `getUpdates` request, so a stream created again may
+/// yield the last updates received before once more. If an offset file is
+/// configured, the offset is written as soon as an update is consumed, so
+/// that a restarted stream starts from the first update which has not been
+/// consumed yet.
+///
+/// Transient errors, e.g. network failures, are waited out. The stream ends
+/// if Telegram rejects polling for good, e.g. because the token is invalid or
+/// a webhook is set, and the error is kept for [`UpdateStream::take_error`].
+/// Updates which cannot be decoded are skipped.
+pub struct UpdateStream {
+    inner: Pin<Box<dyn Stream<Item = Update> + Send>>,
+    error: Arc<Mutex<Option<Error>>>,
+}
+
+struct State {
+    bot: Bot,
+    timeout: Duration,
+    offset: Option<i64>,
+    offset_file: Option<PathBuf>,
+    persisted_offset: Option<i64>,
+    buffer: VecDeque<serde_json::Value>,
+
+    /// The error which ended the stream.
+    error: Arc<Mutex<Option<Error>>>,
+}
+
+impl UpdateStream {
+    /// Creates a new `UpdateStream` receiving updates of `bot`, holding each
+    /// `getUpdates` request for up to `timeout`.
+    pub fn new(bot: Bot, timeout: Duration) -> Self {
+        Self::with_offset(bot, timeout, None, None)
+    }
+
+    /// Creates a new `UpdateStream` which keeps its offset in the file at
+    /// `path`, so that updates are not processed again after a restart.
+    ///
+    /// The file is written whenever an update has been consumed, i.e. the
+    /// stream is polled for the next one. The stream ends if the file cannot
+    /// be written.
+    pub fn with_offset_file<P>(bot: Bot, timeout: Duration, path: P) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let offset = read_offset(&path)?;
+
+        Ok(Self::with_offset(bot, timeout, offset, Some(path)))
+    }
+
+    fn with_offset(
+        bot: Bot,
+        timeout: Duration,
+        offset: Option<i64>,
+        offset_file: Option<PathBuf>,
+    ) -> Self {
+        let error = Arc::new(Mutex::new(None));
+        let state = State {
+            bot,
+            timeout,
+            offset,
+            offset_file,
+            persisted_offset: offset,
+            buffer: VecDeque::new(),
+            error: Arc::clone(&error),
+        };
+
+        Self {
+            inner: Box::pin(stream::unfold(state, next_update)),
+            error,
+        }
+    }
+
+    /// Returns the error which ended the stream, if it has ended because of
+    /// an error.
+    ///
+    /// ```no_run
+    /// # async fn run(bot: rustygram::bot::Bot) {
+    /// use rustygram::{dispatcher::Dispatcher, polling::UpdateStream};
+    /// use std::time::Duration;
+    ///
+    /// let mut updates = UpdateStream::new(bot.clone(), Duration::from_secs(10));
+    /// Dispatcher::new(bot).run(&mut updates).await;
+    /// if let Some(err) = updates.take_error() {
+    ///     eprintln!("polling stopped: {err}");
+    /// }
+    /// # }
+    /// ```
+    pub fn take_error(&mut self) -> Option<Error> {
+        self.error
+            .lock()
+            .expect("update stream error is poisoned")
+            .take()
+    }
+}
+
+impl Stream for UpdateStream {
+    type Item = Update;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+impl fmt::Debug for UpdateStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpdateStream").finish_non_exhaustive()
+    }
+}
+
+async fn next_update(mut state: State) -> Option<(Update, State)> {
+    loop {
+        // Asking for the next update means that everything up to the offset
+        // has been consumed, including skipped updates.
+        if let Err(err) = state.persist_offset() {
+            return state.fail(err);
+        }
+
+        if let Some(update) = state.buffer.pop_front() {
+            // An update the library does not understand must not stop the
+            // stream, it is confirmed without being yielded.
+            let id = update.get("update_id").and_then(serde_json::Value::as_i64);
+            match (serde_json::from_value::<Update>(update), id) {
+                (Ok(update), _) => {
+                    state.offset = Some(update.id + 1);
+                    return Some((update, state));
+                }
+                (Err(_), Some(id)) => state.offset = Some(id + 1),
+                (Err(err), None) => return state.fail(err.into()),
+            }
+            continue;
+        }
+
+        match state.bot.get_updates_as(state.offset, state.timeout).await {
+            Ok(updates) => state.buffer.extend(updates),
+            Err(err) if ErrorClass::of(&err).is_some() => {
+                let delay = err.retry_after().unwrap_or(ERROR_DELAY);
+                tokio::time::sleep(delay).await;
+            }
+            Err(err) => return state.fail(err),
+        }
+    }
+}
+
+impl State {
+    /// Ends the stream because of `err`.
+    fn fail(&self, err: Error) -> Option<(Update, State)> {
+        *self.error.lock().expect("update stream error is poisoned") = Some(err);
+        None
+    }
+
+    fn persist_offset(&mut self) -> Result<(), Error> {
+        let (path, offset) = match (&self.offset_file, self.offset) {
+            (Some(path), Some(offset)) if self.persisted_offset != Some(offset) => (path, offset),
+            _ => return Ok(()),
+        };
+
+        write_offset(path, offset)?;
+        self.persisted_offset = Some(offset);
+        Ok(())
+    }
+}
+
+fn read_offset(path: &Path) -> Result<Option<i64>, Error> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    match contents.trim() {
+        "" => Ok(None),
+        offset => offset.parse().map(Some).map_err(|_| {
+            Error::InvalidInput(format!("invalid offset in {}: {offset}", path.display()))
+        }),
+    }
+}
+
+/// Writes `offset` to `path`, replacing the file atomically.
+fn write_offset(path: &Path, offset: i64) -> Result<(), Error> {
+    let mut tmp_path = path.to_path_buf();
+    tmp_path.set_extension("tmp");
+
+    fs::write(&tmp_path, offset.to_string())?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    use tempfile::tempdir;
+
+    use crate::{
+        bot::Bot,
+        errors::Error,
+        polling::{next_update, read_offset, write_offset, State, DEFAULT_POLLING_TIMEOUT},
+    };
+
+    fn state(updates: Vec<serde_json::Value>) -> State {
+        State {
+            bot: Bot::new("token", "-1"),
+            timeout: DEFAULT_POLLING_TIMEOUT,
+            offset: None,
+            offset_file: None,
+            persisted_offset: None,
+            buffer: VecDeque::from(updates),
+            error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    #[tokio::test]
+    async fn undecodable_updates_are_skipped() {
+        let state = state(vec![
+            serde_json::json!({ "update_id": 5, "message": "not a message" }),
+            serde_json::json!({ "update_id": 6 }),
+        ]);
+
+        let (update, state) = next_update(state).await.unwrap();
+        assert_eq!(update.id, 6);
+        assert_eq!(state.offset, Some(7));
+        assert!(state.error.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn error_ending_the_stream_is_kept() {
+        let state = state(vec![serde_json::json!({ "message": {} })]);
+        let error = Arc::clone(&state.error);
+
+        assert!(next_update(state).await.is_none());
+        assert!(matches!(*error.lock().unwrap(), Some(Error::Json(_))));
+    }
+
+    #[test]
+    fn offset_file_round_trips() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("offset");
+
+        assert_eq!(read_offset(&path).unwrap(), None);
+
+        write_offset(&path, 10001).unwrap();
+        assert_eq!(read_offset(&path).unwrap(), Some(10001));
+
+        std::fs::write(&path, "garbage").unwrap();
+        assert!(read_offset(&path).is_err());
+    }
+
+    #[tokio::test]
+    async fn offset_is_persisted_once_update_is_consumed() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("offset");
+
+        let mut state = state(vec![
+            serde_json::json!({ "update_id": 5 }),
+            serde_json::json!({ "update_id": 6 }),
+        ]);
+        state.offset_file = Some(path.clone());
+
+        let (update, state) = next_update(state).await.unwrap();
+        assert_eq!(update.id, 5);
+        assert_eq!(read_offset(&path).unwrap(), None);
+
+        let (update, _) = next_update(state).await.unwrap();
+        assert_eq!(update.id, 6);
+        assert_eq!(read_offset(&path).unwrap(), Some(6));
+    }
+}
@@ -0,0 +1,491 @@
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+
+use crate::{
+    bot::Bot,
+    callbacks::Callbacks,
+    errors::Error,
+    types::{ChatId, InlineKeyboardMarkup, Message, MessageEntity, MessageId, SendMessageOption},
+};
+
+/// The requests the background tasks of this crate send, e.g. of a
+/// [`StatusBoard`], an [`AckableAlert`] or an [`Escalation`], so that they
+/// can be run against a mock in tests.
+///
+/// The futures are boxed, as `async fn` in traits requires Rust 1.75.
+///
+/// [`StatusBoard`]: crate::status_board::StatusBoard
+/// [`AckableAlert`]: crate::alert::AckableAlert
+/// [`Escalation`]: crate::escalation::Escalation
+pub(crate) trait Requester: Clone + Send + Sync + 'static {
+    /// See [`Bot::for_chat`].
+    fn for_chat(&self, chat_id: ChatId) -> Self;
+
+    /// Returns the routes of the callback queries of the bot.
+    fn callbacks(&self) -> &Arc<Callbacks>;
+
+    /// See [`Bot::migrated_chat_id`].
+    fn migrated_chat_id(&self) -> Option<i64>;
+
+    /// See [`Bot::send_message`].
+    fn send_message<'a>(
+        &'a self,
+        text: &'a str,
+        options: Option<SendMessageOption>,
+    ) -> BoxFuture<'a, Result<Message, Error>>;
+
+    /// See [`Bot::edit_message_text`].
+    fn edit_message_text<'a>(
+        &'a self,
+        message_id: MessageId,
+        text: &'a str,
+        options: Option<SendMessageOption>,
+    ) -> BoxFuture<'a, Result<Message, Error>>;
+
+    /// See [`Bot::edit_message_entities`].
+    fn edit_message_entities<'a>(
+        &'a self,
+        message_id: MessageId,
+        text: &'a str,
+        entities: &'a [MessageEntity],
+        reply_markup: Option<InlineKeyboardMarkup>,
+    ) -> BoxFuture<'a, Result<Message, Error>>;
+
+    /// See [`Bot::edit_message_reply_markup`].
+    fn edit_message_reply_markup<'a>(
+        &'a self,
+        message_id: MessageId,
+        reply_markup: Option<InlineKeyboardMarkup>,
+    ) -> BoxFuture<'a, Result<Message, Error>>;
+
+    /// See [`Bot::answer_callback_query`].
+    fn answer_callback_query<'a>(
+        &'a self,
+        callback_query_id: &'a str,
+        text: Option<&'a str>,
+        show_alert: bool,
+    ) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+impl Requester for Bot {
+    fn for_chat(&self, chat_id: ChatId) -> Self {
+        Self::for_chat(self, chat_id)
+    }
+
+    fn callbacks(&self) -> &Arc<Callbacks> {
+        Self::callbacks(self)
+    }
+
+    fn migrated_chat_id(&self) -> Option<i64> {
+        Self::migrated_chat_id(self)
+    }
+
+    fn send_message<'a>(
+        &'a self,
+        text: &'a str,
+        options: Option<SendMessageOption>,
+    ) -> BoxFuture<'a, Result<Message, Error>> {
+        Box::pin(Self::send_message(self, text, options))
+    }
+
+    fn edit_message_text<'a>(
+        &'a self,
+        message_id: MessageId,
+        text: &'a str,
+        options: Option<SendMessageOption>,
+    ) -> BoxFuture<'a, Result<Message, Error>> {
+        Box::pin(Self::edit_message_text(self, message_id, text, options))
+    }
+
+    fn edit_message_entities<'a>(
+        &'a self,
+        message_id: MessageId,
+        text: &'a str,
+        entities: &'a [MessageEntity],
+        reply_markup: Option<InlineKeyboardMarkup>,
+    ) -> BoxFuture<'a, Result<Message, Error>> {
+        Box::pin(Self::edit_message_entities(
+            self,
+            message_id,
+            text,
+            entities,
+            reply_markup,
+        ))
+    }
+
+    fn edit_message_reply_markup<'a>(
+        &'a self,
+        message_id: MessageId,
+        reply_markup: Option<InlineKeyboardMarkup>,
+    ) -> BoxFuture<'a, Result<Message, Error>> {
+        Box::pin(Self::edit_message_reply_markup(
+            self,
+            message_id,
+            reply_markup,
+        ))
+    }
+
+    fn answer_callback_query<'a>(
+        &'a self,
+        callback_query_id: &'a str,
+        text: Option<&'a str>,
+        show_alert: bool,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(Self::answer_callback_query(
+            self,
+            callback_query_id,
+            text,
+            show_alert,
+        ))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::{
+        collections::{HashMap, VecDeque},
+        sync::{
+            atomic::{AtomicI32, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use futures_util::future::BoxFuture;
+
+    use crate::{
+        callbacks::Callbacks,
+        errors::Error,
+        migration::migrate_to_chat_id,
+        requester::Requester,
+        types::{
+            CallbackQuery, ChatId, InlineKeyboardMarkup, Message, MessageEntity, MessageId,
+            ReplyMarkup, ResponseParameters, SendMessageOption, User,
+        },
+    };
+
+    /// Returns an error of the Telegram Bot API.
+    pub(crate) fn api_error(
+        error_code: i32,
+        description: &str,
+        parameters: Option<ResponseParameters>,
+    ) -> Error {
+        Error::Api {
+            error_code,
+            description: description.to_owned(),
+            parameters,
+        }
+    }
+
+    /// Returns the parameters of an error asking to wait `secs` seconds
+    /// before repeating the request.
+    pub(crate) fn retry_after(secs: u32) -> Option<ResponseParameters> {
+        Some(ResponseParameters {
+            migrate_to_chat_id: None,
+            retry_after: Some(secs),
+        })
+    }
+
+    /// Returns the user @alice with the id `id`.
+    pub(crate) fn user(id: u64) -> User {
+        User {
+            id,
+            is_bot: false,
+            first_name: "Alice".to_owned(),
+            last_name: None,
+            username: Some("alice".to_owned()),
+            language_code: None,
+        }
+    }
+
+    /// Returns a callback query of a button with `data` pressed by the user
+    /// with the id `user_id`.
+    pub(crate) fn query(user_id: u64, data: &str) -> CallbackQuery {
+        CallbackQuery {
+            id: "1".to_owned(),
+            from: user(user_id),
+            message: None,
+            inline_message_id: None,
+            chat_instance: "42".to_owned(),
+            data: Some(data.to_owned()),
+        }
+    }
+
+    /// A request received by a [`MockBot`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub(crate) enum Request {
+        SendMessage {
+            chat_id: String,
+            text: String,
+            options: Option<SendMessageOption>,
+        },
+        EditMessageText {
+            chat_id: String,
+            message_id: MessageId,
+            text: String,
+        },
+        EditMessageEntities {
+            chat_id: String,
+            message_id: MessageId,
+            text: String,
+            reply_markup: Option<InlineKeyboardMarkup>,
+        },
+        EditMessageReplyMarkup {
+            chat_id: String,
+            message_id: MessageId,
+            reply_markup: Option<InlineKeyboardMarkup>,
+        },
+        AnswerCallbackQuery {
+            callback_query_id: String,
+            text: Option<String>,
+        },
+    }
+
+    /// Records requests instead of sending them, and answers them with
+    /// messages numbered from 1 or with the errors queued by
+    /// [`MockBot::fail_next`].
+    ///
+    /// Like a [`Bot`], new messages follow the migration of a chat to a
+    /// supergroup.
+    ///
+    /// [`Bot`]: crate::bot::Bot
+    #[derive(Debug, Clone)]
+    pub(crate) struct MockBot {
+        chat_id: String,
+        latency: Duration,
+        shared: Arc<Shared>,
+    }
+
+    #[derive(Debug, Default)]
+    struct Shared {
+        requests: Mutex<Vec<Request>>,
+
+        /// Every request received, also the ones taken already.
+        history: Mutex<Vec<Request>>,
+        errors: Mutex<VecDeque<Error>>,
+        last_message_id: AtomicI32,
+        callbacks: Arc<Callbacks>,
+        migrations: Mutex<HashMap<String, i64>>,
+    }
+
+    impl MockBot {
+        pub(crate) fn new(chat_id: &str) -> Self {
+            Self {
+                chat_id: chat_id.to_owned(),
+                latency: Duration::ZERO,
+                shared: Arc::default(),
+            }
+        }
+
+        /// Makes every request take `latency` to be answered.
+        pub(crate) fn with_latency(mut self, latency: Duration) -> Self {
+            self.latency = latency;
+            self
+        }
+
+        /// Makes the next request fail with an API error with `description`.
+        pub(crate) fn fail_next(&self, description: &str) {
+            self.fail_next_with(api_error(400, description, None));
+        }
+
+        /// Makes the next request fail with `err`.
+        pub(crate) fn fail_next_with(&self, err: Error) {
+            self.shared.errors.lock().unwrap().push_back(err);
+        }
+
+        /// Returns the requests received so far, and forgets them.
+        pub(crate) fn take_requests(&self) -> Vec<Request> {
+            std::mem::take(&mut *self.shared.requests.lock().unwrap())
+        }
+
+        /// Presses the button labeled `text` of the last inline keyboard sent,
+        /// and returns whether the query has been routed.
+        pub(crate) fn press(&self, text: &str, from: &User) -> bool {
+            let history = self.shared.history.lock().unwrap().clone();
+            let data = history
+                .iter()
+                .rev()
+                .find_map(|request| match request {
+                    Request::SendMessage {
+                        options: Some(options),
+                        ..
+                    } => match &options.reply_markup {
+                        Some(ReplyMarkup::InlineKeyboard(markup)) => Some(markup),
+                        _ => None,
+                    },
+                    Request::EditMessageEntities { reply_markup, .. }
+                    | Request::EditMessageReplyMarkup { reply_markup, .. } => reply_markup.as_ref(),
+                    _ => None,
+                })
+                .into_iter()
+                .flat_map(|markup| markup.inline_keyboard.iter().flatten())
+                .find(|button| button.text == text)
+                .and_then(|button| button.callback_data.clone());
+
+            let query = CallbackQuery {
+                id: format!("query-{}", history.len()),
+                from: from.clone(),
+                message: None,
+                inline_message_id: None,
+                chat_instance: "instance".to_owned(),
+                data,
+            };
+            self.shared.callbacks.route(&query)
+        }
+
+        async fn receive(&self, request: Request) -> Result<(), Error> {
+            if !self.latency.is_zero() {
+                tokio::time::sleep(self.latency).await;
+            }
+
+            self.shared.history.lock().unwrap().push(request.clone());
+            self.shared.requests.lock().unwrap().push(request);
+            match self.shared.errors.lock().unwrap().pop_front() {
+                Some(err) => Err(err),
+                None => Ok(()),
+            }
+        }
+
+        fn message(&self, message_id: MessageId, text: &str) -> Message {
+            serde_json::from_value(serde_json::json!({
+                "message_id": message_id,
+                "date": 0,
+                "chat": { "id": self.chat_id.parse::<i64>().unwrap_or(0), "type": "supergroup" },
+                "text": text,
+            }))
+            .unwrap()
+        }
+    }
+
+    impl Requester for MockBot {
+        fn for_chat(&self, chat_id: ChatId) -> Self {
+            Self {
+                chat_id: chat_id.to_string(),
+                latency: self.latency,
+                shared: Arc::clone(&self.shared),
+            }
+        }
+
+        fn callbacks(&self) -> &Arc<Callbacks> {
+            &self.shared.callbacks
+        }
+
+        fn migrated_chat_id(&self) -> Option<i64> {
+            self.shared
+                .migrations
+                .lock()
+                .unwrap()
+                .get(&self.chat_id)
+                .copied()
+        }
+
+        fn send_message<'a>(
+            &'a self,
+            text: &'a str,
+            options: Option<SendMessageOption>,
+        ) -> BoxFuture<'a, Result<Message, Error>> {
+            Box::pin(async move {
+                let mut chat_id = match self.migrated_chat_id() {
+                    Some(to) => to.to_string(),
+                    None => self.chat_id.clone(),
+                };
+                let mut migrated = false;
+
+                loop {
+                    let request = Request::SendMessage {
+                        chat_id: chat_id.clone(),
+                        text: text.to_owned(),
+                        options: options.clone(),
+                    };
+                    match self.receive(request).await {
+                        Ok(()) => break,
+                        Err(err) if !migrated => match migrate_to_chat_id(&err) {
+                            Some(to) => {
+                                let mut migrations = self.shared.migrations.lock().unwrap();
+                                migrations.insert(chat_id.clone(), to);
+                                chat_id = to.to_string();
+                                migrated = true;
+                            }
+                            None => return Err(err),
+                        },
+                        Err(err) => return Err(err),
+                    }
+                }
+
+                let message_id = self.shared.last_message_id.fetch_add(1, Ordering::SeqCst) + 1;
+                Ok(self.message(MessageId(message_id), text))
+            })
+        }
+
+        fn edit_message_text<'a>(
+            &'a self,
+            message_id: MessageId,
+            text: &'a str,
+            _options: Option<SendMessageOption>,
+        ) -> BoxFuture<'a, Result<Message, Error>> {
+            Box::pin(async move {
+                self.receive(Request::EditMessageText {
+                    chat_id: self.chat_id.clone(),
+                    message_id,
+                    text: text.to_owned(),
+                })
+                .await?;
+
+                Ok(self.message(message_id, text))
+            })
+        }
+
+        fn edit_message_entities<'a>(
+            &'a self,
+            message_id: MessageId,
+            text: &'a str,
+            _entities: &'a [MessageEntity],
+            reply_markup: Option<InlineKeyboardMarkup>,
+        ) -> BoxFuture<'a, Result<Message, Error>> {
+            Box::pin(async move {
+                self.receive(Request::EditMessageEntities {
+                    chat_id: self.chat_id.clone(),
+                    message_id,
+                    text: text.to_owned(),
+                    reply_markup,
+                })
+                .await?;
+
+                Ok(self.message(message_id, text))
+            })
+        }
+
+        fn edit_message_reply_markup<'a>(
+            &'a self,
+            message_id: MessageId,
+            reply_markup: Option<InlineKeyboardMarkup>,
+        ) -> BoxFuture<'a, Result<Message, Error>> {
+            Box::pin(async move {
+                self.receive(Request::EditMessageReplyMarkup {
+                    chat_id: self.chat_id.clone(),
+                    message_id,
+                    reply_markup,
+                })
+                .await?;
+
+                Ok(self.message(message_id, ""))
+            })
+        }
+
+        fn answer_callback_query<'a>(
+            &'a self,
+            callback_query_id: &'a str,
+            text: Option<&'a str>,
+            _show_alert: bool,
+        ) -> BoxFuture<'a, Result<(), Error>> {
+            Box::pin(async move {
+                self.receive(Request::AnswerCallbackQuery {
+                    callback_query_id: callback_query_id.to_owned(),
+                    text: text.map(str::to_owned),
+                })
+                .await
+            })
+        }
+    }
+}
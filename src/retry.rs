@@ -0,0 +1,234 @@
+use std::time::Duration;
+
+use crate::errors::Error;
+
+/// Describes when and how failed requests are repeated.
+///
+/// The policy is applied by [`Bot`] to every call of the Telegram Bot API.
+/// A failed request is repeated if its error falls into one of the
+/// [`retry_on`] classes and fewer than [`max_attempts`] attempts have been
+/// made. Delays between attempts grow exponentially, starting at
+/// [`base_delay`] and capped by [`max_delay`].
+///
+/// [`Bot`]: crate::bot::Bot
+/// [`retry_on`]: RetryPolicy::retry_on
+/// [`max_attempts`]: RetryPolicy::max_attempts
+/// [`base_delay`]: RetryPolicy::base_delay
+/// [`max_delay`]: RetryPolicy::max_delay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. `1` disables
+    /// retries.
+    pub max_attempts: u32,
+
+    /// Delay before the first retry.
+    pub base_delay: Duration,
+
+    /// Upper bound for the delay between two attempts.
+    pub max_delay: Duration,
+
+    /// Randomize delays, so that many clients failing at the same time do not
+    /// retry at the same time either.
+    pub jitter: bool,
+
+    /// Classes of errors which are worth repeating the request for.
+    pub retry_on: Vec<ErrorClass>,
+}
+
+/// Class of a transient error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// The request timed out.
+    ///
+    /// Note that Telegram might have processed a request even though the
+    /// response did not arrive in time, so retrying may deliver a message
+    /// twice.
+    Timeout,
+
+    /// Any other transport level error, e.g. the connection failed.
+    Network,
+
+    /// Telegram answered with a 5xx error.
+    ServerError,
+
+    /// Telegram's flood control rejected the request (HTTP 429).
+    ///
+    /// The request is repeated after the `retry_after` interval requested by
+    /// Telegram rather than after the backoff delay.
+    FloodControl,
+}
+
+impl RetryPolicy {
+    /// Creates a policy which never repeats a request.
+    #[must_use]
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Returns how long to wait before making attempt number `attempt + 1`
+    /// after attempt number `attempt` (starting from 1) failed with `err`.
+    ///
+    /// Returns `None` if the request must not be repeated.
+    #[must_use]
+    pub fn delay_for(&self, err: &Error, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        let class = ErrorClass::of(err)?;
+        if !self.retry_on.contains(&class) {
+            return None;
+        }
+
+        match err.retry_after() {
+            Some(retry_after) => Some(retry_after),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    /// Returns the backoff delay after attempt number `attempt` failed.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = attempt.saturating_sub(1).min(31);
+        let delay = self.base_delay.saturating_mul(1 << exp).min(self.max_delay);
+
+        if !self.jitter {
+            return delay;
+        }
+
+        // Equal jitter: keep at least a half of the delay, randomize the rest.
+        let half = delay / 2;
+        half + half.mul_f64(fastrand::f64())
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Retries timeouts, network and server errors up to 3 attempts in total,
+    /// starting with a 500ms delay capped at 10s, with jitter.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+            retry_on: vec![
+                ErrorClass::Timeout,
+                ErrorClass::Network,
+                ErrorClass::ServerError,
+            ],
+        }
+    }
+}
+
+impl ErrorClass {
+    /// Returns the class of `err`, or `None` if the error is not transient.
+    #[must_use]
+    pub fn of(err: &Error) -> Option<Self> {
+        match err {
+            Error::Network(err) if err.is_timeout() => Some(Self::Timeout),
+            Error::Network(err) if err.is_builder() => None,
+            Error::Network(_) => Some(Self::Network),
+            Error::Api {
+                error_code: 429, ..
+            } => Some(Self::FloodControl),
+            Error::Api { error_code, .. } if (500..600).contains(error_code) => {
+                Some(Self::ServerError)
+            }
+            Error::Api { .. } | Error::Io(_) | Error::Json(_) | Error::InvalidInput(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        errors::Error,
+        requester::mock::{api_error, retry_after},
+        retry::{ErrorClass, RetryPolicy},
+    };
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_max_delay() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn jitter_keeps_delay_within_bounds() {
+        let policy = RetryPolicy::default();
+
+        for _ in 0..100 {
+            let delay = policy.backoff(2);
+            assert!(delay >= Duration::from_millis(500));
+            assert!(delay <= Duration::from_millis(1000));
+        }
+    }
+
+    #[test]
+    fn delay_for_respects_error_classes_and_attempts() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+
+        assert_eq!(
+            policy.delay_for(&api_error(502, "Bad Gateway", None), 1),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            policy.delay_for(&api_error(502, "Bad Gateway", None), 3),
+            None
+        );
+        assert_eq!(
+            policy.delay_for(&api_error(400, "Bad Request", None), 1),
+            None
+        );
+        assert_eq!(
+            policy.delay_for(
+                &api_error(429, "Too Many Requests: retry after 3", retry_after(3)),
+                1
+            ),
+            None
+        );
+        assert_eq!(
+            policy.delay_for(&Error::InvalidInput("test".to_owned()), 1),
+            None
+        );
+        assert_eq!(
+            RetryPolicy::none().delay_for(&api_error(502, "Bad Gateway", None), 1),
+            None
+        );
+    }
+
+    #[test]
+    fn flood_control_waits_for_retry_after() {
+        let policy = RetryPolicy {
+            retry_on: vec![ErrorClass::FloodControl],
+            ..RetryPolicy::default()
+        };
+
+        assert_eq!(
+            policy.delay_for(
+                &api_error(429, "Too Many Requests: retry after 3", retry_after(3)),
+                1
+            ),
+            Some(Duration::from_secs(3))
+        );
+    }
+}
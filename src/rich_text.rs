@@ -0,0 +1,609 @@
+use std::ops::Range;
+
+use crate::{
+    errors::Error,
+    markup,
+    types::{MessageEntity, MessageText},
+    utils::{escape_html, escape_markdown_v2, escape_markdown_v2_code, escape_markdown_v2_url},
+};
+
+/// Formatted text, built piece by piece instead of formatting MarkdownV2 or
+/// HTML by hand, so that dynamic content cannot break the formatting.
+///
+/// The text is sent with explicit entities by [`Bot::send_message`], and can
+/// be rendered to MarkdownV2 or HTML as well:
+///
+/// ```
+/// use rustygram::rich_text::RichText;
+///
+/// let text = RichText::new()
+///     .bold("BTC-PERP")
+///     .plain(" filled at ")
+///     .code("63,250.5")
+///     .plain(" ")
+///     .italic(RichText::new().plain("by ").bold("basis-1"));
+///
+/// assert_eq!(text.text(), "BTC-PERP filled at 63,250.5 by basis-1");
+/// assert_eq!(
+///     text.to_markdown_v2(),
+///     r"*BTC\-PERP* filled at `63,250.5` _by *basis\-1*_"
+/// );
+/// assert_eq!(
+///     text.to_html(),
+///     "<b>BTC-PERP</b> filled at <code>63,250.5</code> <i>by <b>basis-1</b></i>"
+/// );
+/// ```
+///
+/// [`Bot::send_message`]: crate::bot::Bot::send_message
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct RichText {
+    text: String,
+
+    /// Entities ordered by offset, enclosing entities before enclosed ones.
+    entities: Vec<MessageEntity>,
+
+    /// Length of the text in UTF-16 code units.
+    len: usize,
+
+    /// Whether the text ends with a quote, so that the next text has to start
+    /// on a new line.
+    ends_with_quote: bool,
+}
+
+/// Markup a [`RichText`] is rendered to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Markup {
+    MarkdownV2,
+    Html,
+}
+
+impl RichText {
+    /// Creates a new, empty `RichText`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `RichText` from text formatted with `entities`, e.g. the
+    /// text and entities of a received [`Message`].
+    ///
+    /// [`Message`]: crate::types::Message
+    pub fn from_entities<S>(text: S, mut entities: Vec<MessageEntity>) -> Self
+    where
+        S: Into<String>,
+    {
+        let text = text.into();
+        entities.sort_by_key(|entity| (entity.offset, std::cmp::Reverse(entity.length)));
+
+        Self {
+            len: text.encode_utf16().count(),
+            text,
+            entities,
+            ends_with_quote: false,
+        }
+    }
+
+    /// Parses MarkdownV2 the way Telegram does.
+    ///
+    /// Returns [`Error::InvalidInput`] if entities are not closed or overlap.
+    /// Reserved characters which cannot start an entity, like `.` or `-`, are
+    /// accepted unescaped.
+    pub fn from_markdown_v2(markdown: &str) -> Result<Self, Error> {
+        markup::parse_markdown_v2(markdown)
+    }
+
+    /// Parses HTML the way Telegram does.
+    ///
+    /// Returns [`Error::InvalidInput`] if tags are not supported by Telegram,
+    /// not closed or overlap.
+    pub fn from_html(html: &str) -> Result<Self, Error> {
+        markup::parse_html(html)
+    }
+
+    /// Appends unformatted text.
+    #[must_use]
+    pub fn plain(mut self, text: &str) -> Self {
+        self.push_str(text);
+        self
+    }
+
+    /// Appends `text`, keeping its formatting.
+    #[must_use]
+    pub fn append<T>(mut self, text: T) -> Self
+    where
+        T: Into<Self>,
+    {
+        self.extend(text.into());
+        self
+    }
+
+    /// Appends bold text.
+    #[must_use]
+    pub fn bold<T>(self, text: T) -> Self
+    where
+        T: Into<Self>,
+    {
+        self.wrap("bold", text.into(), |_| ())
+    }
+
+    /// Appends italic text.
+    #[must_use]
+    pub fn italic<T>(self, text: T) -> Self
+    where
+        T: Into<Self>,
+    {
+        self.wrap("italic", text.into(), |_| ())
+    }
+
+    /// Appends underlined text.
+    #[must_use]
+    pub fn underline<T>(self, text: T) -> Self
+    where
+        T: Into<Self>,
+    {
+        self.wrap("underline", text.into(), |_| ())
+    }
+
+    /// Appends strikethrough text.
+    #[must_use]
+    pub fn strike<T>(self, text: T) -> Self
+    where
+        T: Into<Self>,
+    {
+        self.wrap("strikethrough", text.into(), |_| ())
+    }
+
+    /// Appends text hidden until the user taps on it.
+    #[must_use]
+    pub fn spoiler<T>(self, text: T) -> Self
+    where
+        T: Into<Self>,
+    {
+        self.wrap("spoiler", text.into(), |_| ())
+    }
+
+    /// Appends inline fixed-width code.
+    #[must_use]
+    pub fn code(self, code: &str) -> Self {
+        self.wrap("code", code.into(), |_| ())
+    }
+
+    /// Appends a block of fixed-width code, highlighted as `language` if set.
+    #[must_use]
+    pub fn pre(self, code: &str, language: Option<&str>) -> Self {
+        self.wrap("pre", code.into(), |entity| {
+            entity.language = language.map(str::to_owned);
+        })
+    }
+
+    /// Appends text linking to `url`.
+    #[must_use]
+    pub fn link<T>(self, text: T, url: &str) -> Self
+    where
+        T: Into<Self>,
+    {
+        self.wrap("text_link", text.into(), |entity| {
+            entity.url = Some(url.to_owned());
+        })
+    }
+
+    /// Appends text mentioning the user `user_id`, e.g. to notify the person
+    /// on call, even if the user has no username.
+    #[must_use]
+    pub fn mention<T>(self, text: T, user_id: u64) -> Self
+    where
+        T: Into<Self>,
+    {
+        self.link(text, &format!("tg://user?id={user_id}"))
+    }
+
+    /// Appends a custom emoji, shown as the regular `emoji` where custom
+    /// emoji are not available.
+    #[must_use]
+    pub fn custom_emoji(self, emoji: &str, custom_emoji_id: &str) -> Self {
+        self.wrap("custom_emoji", emoji.into(), |entity| {
+            entity.custom_emoji_id = Some(custom_emoji_id.to_owned());
+        })
+    }
+
+    /// Appends a quote.
+    ///
+    /// A quote takes whole lines: it starts on a new line, and the text
+    /// appended after it starts on a new line as well.
+    #[must_use]
+    pub fn blockquote<T>(self, text: T) -> Self
+    where
+        T: Into<Self>,
+    {
+        self.quote("blockquote", text.into())
+    }
+
+    /// Appends a quote which is collapsed until the user expands it, see
+    /// [`RichText::blockquote`].
+    #[must_use]
+    pub fn expandable_blockquote<T>(self, text: T) -> Self
+    where
+        T: Into<Self>,
+    {
+        self.quote("expandable_blockquote", text.into())
+    }
+
+    /// Returns the text without formatting.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the entities formatting the text, with offsets and lengths in
+    /// UTF-16 code units as expected by Telegram.
+    #[must_use]
+    pub fn entities(&self) -> &[MessageEntity] {
+        &self.entities
+    }
+
+    /// Returns whether the text is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Returns the length of the text in UTF-16 code units, as counted by
+    /// Telegram's length limits.
+    #[must_use]
+    pub fn len_utf16(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the part of the text within `range` of UTF-16 code units,
+    /// with the entities overlapping it cut to the range.
+    pub(crate) fn slice(&self, range: Range<usize>) -> Self {
+        let start = byte_index(&self.text, range.start);
+        let end = byte_index(&self.text, range.end);
+
+        let entities = self
+            .entities
+            .iter()
+            .filter_map(|entity| {
+                let offset = entity.offset.max(range.start);
+                let end = (entity.offset + entity.length).min(range.end);
+                (offset < end).then(|| MessageEntity {
+                    offset: offset - range.start,
+                    length: end - offset,
+                    ..entity.clone()
+                })
+            })
+            .collect();
+
+        Self::from_entities(&self.text[start..end], entities)
+    }
+
+    /// Renders the text to MarkdownV2, to be sent with
+    /// [`SendMessageParseMode::MarkdownV2`].
+    ///
+    /// [`SendMessageParseMode::MarkdownV2`]: crate::types::SendMessageParseMode::MarkdownV2
+    #[must_use]
+    pub fn to_markdown_v2(&self) -> String {
+        self.render(Markup::MarkdownV2)
+    }
+
+    /// Renders the text to HTML, to be sent with
+    /// [`SendMessageParseMode::HTML`].
+    ///
+    /// [`SendMessageParseMode::HTML`]: crate::types::SendMessageParseMode::HTML
+    #[must_use]
+    pub fn to_html(&self) -> String {
+        self.render(Markup::Html)
+    }
+
+    fn push_str(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        self.start_line_after_quote(text);
+        self.text.push_str(text);
+        self.len += text.encode_utf16().count();
+    }
+
+    fn extend(&mut self, text: Self) {
+        if text.is_empty() {
+            return;
+        }
+
+        self.start_line_after_quote(&text.text);
+        let offset = self.len;
+        self.entities
+            .extend(text.entities.into_iter().map(|mut entity| {
+                entity.offset += offset;
+                entity
+            }));
+        self.text.push_str(&text.text);
+        self.len += text.len;
+        self.ends_with_quote = text.ends_with_quote;
+    }
+
+    /// Starts a new line before `next` if the text ends with a quote.
+    fn start_line_after_quote(&mut self, next: &str) {
+        if self.ends_with_quote && !next.starts_with('\n') {
+            self.text.push('\n');
+            self.len += 1;
+        }
+        self.ends_with_quote = false;
+    }
+
+    /// Appends `text` formatted as an entity of type `kind`, which is set up
+    /// by `init`.
+    fn wrap<F>(mut self, kind: &str, text: Self, init: F) -> Self
+    where
+        F: FnOnce(&mut MessageEntity),
+    {
+        if text.is_empty() {
+            return self;
+        }
+
+        self.start_line_after_quote(&text.text);
+        let mut entity = MessageEntity {
+            kind: kind.to_owned(),
+            offset: self.len,
+            length: text.len,
+            url: None,
+            user: None,
+            language: None,
+            custom_emoji_id: None,
+        };
+        init(&mut entity);
+
+        self.entities.push(entity);
+        self.extend(text);
+        self
+    }
+
+    fn quote(mut self, kind: &str, text: Self) -> Self {
+        if text.is_empty() {
+            return self;
+        }
+
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.push_str("\n");
+        }
+
+        let mut quoted = self.wrap(kind, text, |_| ());
+        quoted.ends_with_quote = true;
+        quoted
+    }
+
+    fn render(&self, markup: Markup) -> String {
+        let mut out = String::with_capacity(self.text.len() * 2);
+        let mut open = Vec::<&MessageEntity>::new();
+        let mut entities = self.entities.iter().peekable();
+        let mut offset = 0;
+
+        let mut chars = self.text.chars();
+        loop {
+            while let Some(entity) = open.last() {
+                if entity.offset + entity.length > offset {
+                    break;
+                }
+                push_marker(&mut out, &closing_tag(entity, markup));
+                open.pop();
+            }
+
+            while let Some(entity) = entities.next_if(|entity| entity.offset == offset) {
+                push_marker(&mut out, &opening_tag(entity, markup));
+                open.push(entity);
+            }
+
+            let c = match chars.next() {
+                Some(c) => c,
+                None => break,
+            };
+            offset += c.len_utf16();
+
+            let mut buf = [0; 4];
+            let c = c.encode_utf8(&mut buf);
+            let in_code = open.iter().any(|entity| is_code(entity));
+            let in_quote = open.iter().any(|entity| is_quote(entity));
+            match markup {
+                Markup::MarkdownV2 if c == "\n" && in_quote => out.push_str("\n>"),
+                Markup::MarkdownV2 if in_code => out.push_str(&escape_markdown_v2_code(c)),
+                Markup::MarkdownV2 => out.push_str(&escape_markdown_v2(c)),
+                Markup::Html => out.push_str(&escape_html(c)),
+            }
+        }
+
+        out
+    }
+}
+
+impl From<&str> for RichText {
+    fn from(text: &str) -> Self {
+        Self::new().plain(text)
+    }
+}
+
+impl From<String> for RichText {
+    fn from(text: String) -> Self {
+        Self::new().plain(&text)
+    }
+}
+
+impl From<RichText> for MessageText {
+    fn from(text: RichText) -> Self {
+        Self::with_entities(text.text, text.entities)
+    }
+}
+
+impl From<&RichText> for MessageText {
+    fn from(text: &RichText) -> Self {
+        text.clone().into()
+    }
+}
+
+/// Returns the index of the byte at `offset` UTF-16 code units into `text`.
+fn byte_index(text: &str, offset: usize) -> usize {
+    let mut utf16 = 0;
+    for (index, c) in text.char_indices() {
+        if utf16 >= offset {
+            return index;
+        }
+        utf16 += c.len_utf16();
+    }
+
+    text.len()
+}
+
+fn is_code(entity: &MessageEntity) -> bool {
+    matches!(entity.kind.as_str(), "code" | "pre")
+}
+
+fn is_quote(entity: &MessageEntity) -> bool {
+    matches!(entity.kind.as_str(), "blockquote" | "expandable_blockquote")
+}
+
+/// Appends a formatting marker, separating it from a preceding `_`, as
+/// MarkdownV2 would otherwise read e.g. italic and underline as `___`.
+fn push_marker(out: &mut String, marker: &str) {
+    if out.ends_with('_') && marker.starts_with('_') {
+        out.push('\r');
+    }
+    out.push_str(marker);
+}
+
+fn opening_tag(entity: &MessageEntity, markup: Markup) -> String {
+    let url = entity.url.as_deref().unwrap_or_default();
+    let language = entity.language.as_deref();
+    let custom_emoji_id = entity.custom_emoji_id.as_deref().unwrap_or_default();
+
+    match (markup, entity.kind.as_str()) {
+        (Markup::MarkdownV2, "bold") => "*".to_owned(),
+        (Markup::MarkdownV2, "italic") => "_".to_owned(),
+        (Markup::MarkdownV2, "underline") => "__".to_owned(),
+        (Markup::MarkdownV2, "strikethrough") => "~".to_owned(),
+        (Markup::MarkdownV2, "spoiler") => "||".to_owned(),
+        (Markup::MarkdownV2, "code") => "`".to_owned(),
+        (Markup::MarkdownV2, "pre") => format!("```{}\n", language.unwrap_or_default()),
+        (Markup::MarkdownV2, "text_link") => "[".to_owned(),
+        (Markup::MarkdownV2, "custom_emoji") => "![".to_owned(),
+        (Markup::MarkdownV2, "blockquote") => ">".to_owned(),
+        (Markup::MarkdownV2, "expandable_blockquote") => "**>".to_owned(),
+        (Markup::Html, "bold") => "<b>".to_owned(),
+        (Markup::Html, "italic") => "<i>".to_owned(),
+        (Markup::Html, "underline") => "<u>".to_owned(),
+        (Markup::Html, "strikethrough") => "<s>".to_owned(),
+        (Markup::Html, "spoiler") => "<tg-spoiler>".to_owned(),
+        (Markup::Html, "code") => "<code>".to_owned(),
+        (Markup::Html, "pre") => match language {
+            Some(language) => format!("<pre><code class=\"language-{}\">", escape_html(language)),
+            None => "<pre>".to_owned(),
+        },
+        (Markup::Html, "text_link") => format!("<a href=\"{}\">", escape_html(url)),
+        (Markup::Html, "custom_emoji") => {
+            format!("<tg-emoji emoji-id=\"{}\">", escape_html(custom_emoji_id))
+        }
+        (Markup::Html, "blockquote") => "<blockquote>".to_owned(),
+        (Markup::Html, "expandable_blockquote") => "<blockquote expandable>".to_owned(),
+        _ => String::new(),
+    }
+}
+
+fn closing_tag(entity: &MessageEntity, markup: Markup) -> String {
+    let url = entity.url.as_deref().unwrap_or_default();
+    let custom_emoji_id = entity.custom_emoji_id.as_deref().unwrap_or_default();
+
+    match (markup, entity.kind.as_str()) {
+        (Markup::MarkdownV2, "bold") => "*".to_owned(),
+        (Markup::MarkdownV2, "italic") => "_".to_owned(),
+        (Markup::MarkdownV2, "underline") => "__".to_owned(),
+        (Markup::MarkdownV2, "strikethrough") => "~".to_owned(),
+        (Markup::MarkdownV2, "spoiler") => "||".to_owned(),
+        (Markup::MarkdownV2, "code") => "`".to_owned(),
+        (Markup::MarkdownV2, "pre") => "\n```".to_owned(),
+        (Markup::MarkdownV2, "text_link") => format!("]({})", escape_markdown_v2_url(url)),
+        (Markup::MarkdownV2, "custom_emoji") => format!("](tg://emoji?id={custom_emoji_id})"),
+        (Markup::MarkdownV2, "expandable_blockquote") => "||".to_owned(),
+        (Markup::Html, "bold") => "</b>".to_owned(),
+        (Markup::Html, "italic") => "</i>".to_owned(),
+        (Markup::Html, "underline") => "</u>".to_owned(),
+        (Markup::Html, "strikethrough") => "</s>".to_owned(),
+        (Markup::Html, "spoiler") => "</tg-spoiler>".to_owned(),
+        (Markup::Html, "code") => "</code>".to_owned(),
+        (Markup::Html, "pre") if entity.language.is_some() => "</code></pre>".to_owned(),
+        (Markup::Html, "pre") => "</pre>".to_owned(),
+        (Markup::Html, "text_link") => "</a>".to_owned(),
+        (Markup::Html, "custom_emoji") => "</tg-emoji>".to_owned(),
+        (Markup::Html, "blockquote" | "expandable_blockquote") => "</blockquote>".to_owned(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rich_text::RichText, types::MessageEntity};
+
+    fn entity(kind: &str, offset: usize, length: usize) -> MessageEntity {
+        MessageEntity {
+            kind: kind.to_owned(),
+            offset,
+            length,
+            url: None,
+            user: None,
+            language: None,
+            custom_emoji_id: None,
+        }
+    }
+
+    #[test]
+    fn offsets_are_counted_in_utf16() {
+        let text = RichText::new()
+            .plain("📈 ")
+            .bold("ETH")
+            .plain(" é ")
+            .underline(RichText::new().italic("up"))
+            .link("chart", "https://example.com/a_(b)");
+
+        assert_eq!(text.text(), "📈 ETH é upchart");
+        assert_eq!(
+            text.entities(),
+            [
+                entity("bold", 3, 3),
+                entity("underline", 9, 2),
+                entity("italic", 9, 2),
+                MessageEntity {
+                    url: Some("https://example.com/a_(b)".to_owned()),
+                    ..entity("text_link", 11, 5)
+                },
+            ]
+        );
+        assert_eq!(
+            text.to_markdown_v2(),
+            "📈 *ETH* é __\r_up_\r__[chart](https://example.com/a_(b\\))"
+        );
+        assert_eq!(
+            text.to_html(),
+            "📈 <b>ETH</b> é <u><i>up</i></u><a href=\"https://example.com/a_(b)\">chart</a>"
+        );
+    }
+
+    #[test]
+    fn quotes_and_code_are_rendered() {
+        let text = RichText::new()
+            .plain("Rejected:")
+            .blockquote("price band\n1.5%")
+            .pre("let x = `a`;", Some("rust"))
+            .expandable_blockquote("details");
+
+        assert_eq!(
+            text.text(),
+            "Rejected:\nprice band\n1.5%\nlet x = `a`;\ndetails"
+        );
+        assert_eq!(
+            text.to_markdown_v2(),
+            "Rejected:\n>price band\n>1\\.5%\n```rust\nlet x = \\`a\\`;\n```\n**>details||"
+        );
+        assert_eq!(
+            text.to_html(),
+            "Rejected:\n<blockquote>price band\n1.5%</blockquote>\n\
+             <pre><code class=\"language-rust\">let x = `a`;</code></pre>\n\
+             <blockquote expandable>details</blockquote>"
+        );
+    }
+}
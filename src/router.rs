@@ -0,0 +1,229 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::{
+    bot::Bot,
+    errors::Error,
+    types::{ChatId, Message, SendMessageOption, SendMessageParseMode},
+};
+
+/// Maps logical channels like `"fills"` or `"risk"` to the chats, forum
+/// topics and options their messages are sent with.
+///
+/// Routes are usually loaded from a JSON file mapping channel names to
+/// routes, so that channels can be re-pointed without changing code:
+///
+/// ```json
+/// {
+///     "fills": { "chat_id": -1001234567890, "message_thread_id": 42, "disable_notification": true },
+///     "risk": { "chat_id": "@risk_desk", "parse_mode": "HTML" }
+/// }
+/// ```
+///
+/// All messages are sent through the same [`Bot`], sharing its http-client
+/// and rate limiter.
+#[derive(Debug)]
+pub struct Router {
+    bot: Bot,
+    routes: RwLock<HashMap<String, Route>>,
+    path: Option<PathBuf>,
+}
+
+/// Where and how the messages of a channel are sent.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Route {
+    /// Chat the messages are sent to.
+    pub chat_id: ChatId,
+
+    /// Forum topic the messages are sent to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_thread_id: Option<i32>,
+
+    /// Parse mode of the messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse_mode: Option<SendMessageParseMode>,
+
+    /// Sends the messages silently.
+    #[serde(default)]
+    pub disable_notification: bool,
+}
+
+impl Router {
+    /// Creates a new `Router` without routes, sending through `bot`.
+    pub fn new(bot: Bot) -> Self {
+        Self {
+            bot,
+            routes: RwLock::new(HashMap::new()),
+            path: None,
+        }
+    }
+
+    /// Creates a new `Router` with the routes of the JSON file at `path`,
+    /// which can be read again with [`Router::reload`].
+    pub fn from_file<P>(bot: Bot, path: P) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let routes = read_routes(&path)?;
+
+        Ok(Self {
+            bot,
+            routes: RwLock::new(routes),
+            path: Some(path),
+        })
+    }
+
+    /// Creates a new `Router` with the routes of a JSON document.
+    pub fn from_json(bot: Bot, json: &str) -> Result<Self, Error> {
+        Ok(Self {
+            bot,
+            routes: RwLock::new(serde_json::from_str(json)?),
+            path: None,
+        })
+    }
+
+    /// Adds or replaces the route of `channel`.
+    #[must_use]
+    pub fn with_route<C>(self, channel: C, route: Route) -> Self
+    where
+        C: Into<String>,
+    {
+        self.write().insert(channel.into(), route);
+        self
+    }
+
+    /// Replaces all routes with the ones in the file the router was created
+    /// from. The routes are kept if the file cannot be read.
+    pub fn reload(&self) -> Result<(), Error> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => {
+                return Err(Error::InvalidInput(
+                    "router was not created from a file".to_owned(),
+                ))
+            }
+        };
+
+        let routes = read_routes(path)?;
+        *self.write() = routes;
+        Ok(())
+    }
+
+    /// Returns the route of `channel`.
+    #[must_use]
+    pub fn route(&self, channel: &str) -> Option<Route> {
+        self.read().get(channel).cloned()
+    }
+
+    /// Returns the names of all channels.
+    #[must_use]
+    pub fn channels(&self) -> Vec<String> {
+        let mut channels = self.read().keys().cloned().collect::<Vec<_>>();
+        channels.sort();
+        channels
+    }
+
+    /// Sends `msg` to the chat `channel` is routed to.
+    ///
+    /// Returns [`Error::InvalidInput`] if there is no route for `channel`.
+    pub async fn send(&self, channel: &str, msg: &str) -> Result<Message, Error> {
+        let route = self
+            .route(channel)
+            .ok_or_else(|| Error::InvalidInput(format!("no route for channel {channel:?}")))?;
+
+        self.bot
+            .for_chat(route.chat_id.clone())
+            .send_message(msg, Some(route.options()))
+            .await
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, Route>> {
+        self.routes.read().expect("router routes are poisoned")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<String, Route>> {
+        self.routes.write().expect("router routes are poisoned")
+    }
+}
+
+impl Route {
+    /// Creates a new `Route` sending to `chat_id` with default options.
+    pub fn new<C>(chat_id: C) -> Self
+    where
+        C: Into<ChatId>,
+    {
+        Self {
+            chat_id: chat_id.into(),
+            message_thread_id: None,
+            parse_mode: None,
+            disable_notification: false,
+        }
+    }
+
+    /// Returns the options messages of this route are sent with.
+    #[must_use]
+    pub fn options(&self) -> SendMessageOption {
+        SendMessageOption {
+            parse_mode: self.parse_mode,
+            disable_notification: self.disable_notification,
+            message_thread_id: self.message_thread_id,
+            ..Default::default()
+        }
+    }
+}
+
+fn read_routes(path: &Path) -> Result<HashMap<String, Route>, Error> {
+    let contents = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::{
+        bot::Bot,
+        router::{Route, Router},
+        types::{ChatId, SendMessageParseMode},
+    };
+
+    #[test]
+    fn routes_are_loaded_and_reloaded() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("routes.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "fills": { "chat_id": -100123, "message_thread_id": 7, "disable_notification": true },
+                "risk": { "chat_id": "@risk_desk", "parse_mode": "HTML" }
+            }"#,
+        )
+        .unwrap();
+
+        let router = Router::from_file(Bot::new("token", "-1"), &path).unwrap();
+        assert_eq!(router.channels(), ["fills", "risk"]);
+
+        let fills = router.route("fills").unwrap();
+        assert_eq!(fills.chat_id, ChatId::Id(-100123));
+        assert_eq!(fills.options().message_thread_id, Some(7));
+        assert!(fills.options().disable_notification);
+        assert_eq!(
+            router.route("risk").unwrap().parse_mode,
+            Some(SendMessageParseMode::HTML)
+        );
+
+        std::fs::write(&path, r#"{ "fills": { "chat_id": "-100456" } }"#).unwrap();
+        router.reload().unwrap();
+        assert_eq!(router.route("fills").unwrap(), Route::new(-100456));
+        assert!(router.route("risk").is_none());
+
+        std::fs::write(&path, "{").unwrap();
+        assert!(router.reload().is_err());
+        assert!(router.route("fills").is_some());
+    }
+}
@@ -0,0 +1,369 @@
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use tokio::sync::Notify;
+
+use crate::{bot::Bot, errors::Error, requester::Requester, types::MessageId};
+
+/// A single message in the chat which shows named fields and is edited in
+/// place whenever they change.
+///
+/// Updates are coalesced: after a field changes, the board waits for
+/// `min_interval` and then renders all changes made in the meantime with a
+/// single edit. An edit is skipped if the rendered text has not changed, and
+/// the message is sent anew if an edit finds it deleted from the chat. A
+/// deleted board whose text does not change is only noticed by
+/// [`StatusBoard::flush`].
+///
+/// The message is rendered as the title followed by one `name: value` line
+/// per field, in the order the fields were first set.
+///
+/// `StatusBoard` is cheap to clone, all clones share the same message. The
+/// background task exits once every clone has been dropped.
+#[derive(Debug, Clone)]
+pub struct StatusBoard {
+    handle: Arc<Handle>,
+}
+
+/// Ends the background task once the last `StatusBoard` clone is dropped.
+#[derive(Debug)]
+struct Handle {
+    shared: Arc<Shared<Bot>>,
+}
+
+#[derive(Debug)]
+struct Shared<B> {
+    bot: B,
+    title: String,
+    state: Mutex<State>,
+
+    /// Signaled when a field changes or the board is dropped.
+    changed: Notify,
+
+    /// Serializes pushes, so that concurrent renders do not send two messages.
+    pushing: tokio::sync::Mutex<()>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    fields: Vec<(String, String)>,
+    message_id: Option<MessageId>,
+    sent_text: Option<String>,
+    closed: bool,
+}
+
+impl StatusBoard {
+    /// Creates a new `StatusBoard` with the given `title`, shown in the chat
+    /// of `bot`. Nothing is sent until a field is set.
+    ///
+    /// # Panics
+    /// If called outside of a tokio runtime.
+    pub fn new<T>(bot: Bot, title: T, min_interval: Duration) -> Self
+    where
+        T: Into<String>,
+    {
+        let shared = Arc::new(Shared::new(bot, title.into()));
+        tokio::spawn(run(Arc::clone(&shared), min_interval));
+
+        Self {
+            handle: Arc::new(Handle { shared }),
+        }
+    }
+
+    /// Sets the field `name` to `value`, adding the field if it does not
+    /// exist yet.
+    pub fn set<N, V>(&self, name: N, value: V)
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        self.handle.shared.set(name.into(), value.into());
+    }
+
+    /// Removes the field `name`.
+    pub fn remove(&self, name: &str) {
+        let shared = &self.handle.shared;
+        shared.lock().fields.retain(|(field, _)| field != name);
+        shared.changed.notify_one();
+    }
+
+    /// Renders the board and updates the message right away, without waiting
+    /// for pending updates to be coalesced.
+    ///
+    /// The message is edited even if the text has not changed, so that it is
+    /// sent anew if it has been deleted from the chat.
+    pub async fn flush(&self) -> Result<(), Error> {
+        self.handle.shared.push(true).await
+    }
+
+    /// Returns the id of the message showing the board, if it has been sent.
+    #[must_use]
+    pub fn message_id(&self) -> Option<MessageId> {
+        self.handle.shared.lock().message_id
+    }
+
+    /// Returns the text the board currently renders to.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let shared = &self.handle.shared;
+        render(&shared.title, &shared.lock().fields)
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.changed.notify_one();
+    }
+}
+
+impl<B> Shared<B> {
+    fn new(bot: B, title: String) -> Self {
+        Self {
+            bot,
+            title,
+            state: Mutex::new(State::default()),
+            changed: Notify::new(),
+            pushing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("status board state is poisoned")
+    }
+
+    fn set(&self, name: String, value: String) {
+        {
+            let mut state = self.lock();
+            match state.fields.iter_mut().find(|(field, _)| *field == name) {
+                Some((_, old)) => *old = value,
+                None => state.fields.push((name, value)),
+            }
+        }
+
+        self.changed.notify_one();
+    }
+}
+
+impl<B> Shared<B>
+where
+    B: Requester,
+{
+    /// Makes the message in the chat show the current fields. Unless
+    /// `force` is set, nothing is sent if the text has not changed.
+    async fn push(&self, force: bool) -> Result<(), Error> {
+        let _pushing = self.pushing.lock().await;
+
+        let (text, message_id) = {
+            let state = self.lock();
+            let text = render(&self.title, &state.fields);
+            if (!force && state.sent_text.as_ref() == Some(&text)) || text.is_empty() {
+                return Ok(());
+            }
+            (text, state.message_id)
+        };
+
+        let message_id = match message_id {
+            Some(message_id) => match self.bot.edit_message_text(message_id, &text, None).await {
+                Ok(_) => message_id,
+                Err(err) if is_not_modified(&err) => message_id,
+                Err(err) if is_message_gone(&err) => self.bot.send_message(&text, None).await?.id,
+                Err(err) => return Err(err),
+            },
+            None => self.bot.send_message(&text, None).await?.id,
+        };
+
+        let mut state = self.lock();
+        state.message_id = Some(message_id);
+        state.sent_text = Some(text);
+        Ok(())
+    }
+}
+
+/// Renders updates until the board is dropped.
+async fn run<B>(shared: Arc<Shared<B>>, min_interval: Duration)
+where
+    B: Requester,
+{
+    loop {
+        shared.changed.notified().await;
+        if shared.lock().closed {
+            return;
+        }
+
+        tokio::time::sleep(min_interval).await;
+
+        // A failed update is retried with the next change.
+        let _ = shared.push(false).await;
+    }
+}
+
+fn render(title: &str, fields: &[(String, String)]) -> String {
+    let mut lines = Vec::with_capacity(fields.len() + 1);
+    if !title.is_empty() {
+        lines.push(title.to_owned());
+    }
+    lines.extend(
+        fields
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}")),
+    );
+
+    lines.join("\n")
+}
+
+/// Returns whether an edit failed because the text did not change.
+fn is_not_modified(err: &Error) -> bool {
+    match err {
+        Error::Api { description, .. } => description.contains("message is not modified"),
+        _ => false,
+    }
+}
+
+/// Returns whether an edit failed because the message no longer exists.
+fn is_message_gone(err: &Error) -> bool {
+    match err {
+        Error::Api { description, .. } => {
+            description.contains("message to edit not found")
+                || description.contains("MESSAGE_ID_INVALID")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use crate::{
+        requester::mock::{api_error, MockBot, Request},
+        status_board::{is_message_gone, is_not_modified, render, run, Shared},
+        types::MessageId,
+    };
+
+    const MIN_INTERVAL: Duration = Duration::from_secs(2);
+
+    fn board(bot: &MockBot) -> Arc<Shared<MockBot>> {
+        let shared = Arc::new(Shared::new(bot.clone(), "Positions".to_owned()));
+        tokio::spawn(run(Arc::clone(&shared), MIN_INTERVAL));
+        shared
+    }
+
+    fn send(text: &str) -> Request {
+        Request::SendMessage {
+            chat_id: "-100".to_owned(),
+            text: text.to_owned(),
+            options: None,
+        }
+    }
+
+    fn edit(message_id: i32, text: &str) -> Request {
+        Request::EditMessageText {
+            chat_id: "-100".to_owned(),
+            message_id: MessageId(message_id),
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn render_lists_fields_under_title() {
+        let fields = vec![
+            ("BTC".to_owned(), "long 0.1".to_owned()),
+            ("ETH".to_owned(), "flat".to_owned()),
+        ];
+
+        assert_eq!(
+            render("Positions", &fields),
+            "Positions\nBTC: long 0.1\nETH: flat"
+        );
+        assert_eq!(render("", &fields), "BTC: long 0.1\nETH: flat");
+        assert_eq!(render("", &[]), "");
+    }
+
+    #[test]
+    fn edit_errors_are_recognized() {
+        assert!(is_not_modified(&api_error(
+            400, "Bad Request: message is not modified: specified new message content and reply markup are exactly the same as a current content and reply markup of the message", None
+        )));
+        assert!(is_message_gone(&api_error(
+            400,
+            "Bad Request: message to edit not found",
+            None
+        )));
+        assert!(!is_message_gone(&api_error(
+            400,
+            "Bad Request: chat not found",
+            None
+        )));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn updates_are_coalesced() {
+        let bot = MockBot::new("-100");
+        let shared = board(&bot);
+
+        shared.set("BTC".to_owned(), "long 0.1".to_owned());
+        tokio::time::sleep(MIN_INTERVAL / 2).await;
+        shared.set("ETH".to_owned(), "flat".to_owned());
+        assert_eq!(bot.take_requests(), []);
+
+        tokio::time::sleep(MIN_INTERVAL).await;
+        assert_eq!(
+            bot.take_requests(),
+            [send("Positions\nBTC: long 0.1\nETH: flat")]
+        );
+        assert_eq!(shared.lock().message_id, Some(MessageId(1)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unchanged_text_is_not_edited() {
+        let bot = MockBot::new("-100");
+        let shared = board(&bot);
+        shared.set("BTC".to_owned(), "long 0.1".to_owned());
+        tokio::time::sleep(MIN_INTERVAL * 2).await;
+        bot.take_requests();
+
+        shared.set("BTC".to_owned(), "long 0.1".to_owned());
+        tokio::time::sleep(MIN_INTERVAL * 2).await;
+        assert_eq!(bot.take_requests(), []);
+
+        shared.set("BTC".to_owned(), "flat".to_owned());
+        tokio::time::sleep(MIN_INTERVAL * 2).await;
+        assert_eq!(bot.take_requests(), [edit(1, "Positions\nBTC: flat")]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn deleted_board_is_sent_anew() {
+        let bot = MockBot::new("-100");
+        let shared = board(&bot);
+        shared.set("BTC".to_owned(), "long 0.1".to_owned());
+        tokio::time::sleep(MIN_INTERVAL * 2).await;
+        bot.take_requests();
+
+        bot.fail_next("Bad Request: message to edit not found");
+        shared.set("BTC".to_owned(), "flat".to_owned());
+        tokio::time::sleep(MIN_INTERVAL * 2).await;
+        assert_eq!(
+            bot.take_requests(),
+            [
+                edit(1, "Positions\nBTC: flat"),
+                send("Positions\nBTC: flat")
+            ]
+        );
+        assert_eq!(shared.lock().message_id, Some(MessageId(2)));
+
+        // Flushing edits the board even if its text has not changed.
+        bot.fail_next("Bad Request: message to edit not found");
+        shared.push(true).await.unwrap();
+        assert_eq!(
+            bot.take_requests(),
+            [
+                edit(2, "Positions\nBTC: flat"),
+                send("Positions\nBTC: flat")
+            ]
+        );
+        assert_eq!(shared.lock().message_id, Some(MessageId(3)));
+    }
+}
@@ -4,7 +4,7 @@ mod test {
         types::{SendMessageOption, SendMessageParseMode},
         *,
     }; // import lib.rs
-    use std::{env, sync::Arc};
+    use std::env;
 
     /// Reading bot token, and chat id for inteacting with telegram bot.
     ///
@@ -49,6 +49,7 @@ mod test {
                 Woot\!"#,
             Some(SendMessageOption {
                 parse_mode: Some(SendMessageParseMode::MarkdownV2),
+                ..Default::default()
             }),
         );
 
@@ -60,6 +61,7 @@ mod test {
                 Woot\!"#,
             Some(SendMessageOption {
                 parse_mode: Some(SendMessageParseMode::MarkdownV2),
+                ..Default::default()
             }),
         );
 
@@ -77,6 +79,7 @@ mod test {
                 Woot!"#,
             Some(SendMessageOption {
                 parse_mode: Some(SendMessageParseMode::HTML),
+                ..Default::default()
             }),
         );
         let m2 = bot.send_message(
@@ -86,6 +89,7 @@ mod test {
                     Woot!"#,
             Some(SendMessageOption {
                 parse_mode: Some(SendMessageParseMode::HTML),
+                ..Default::default()
             }),
         );
 
@@ -115,4 +119,31 @@ mod test {
         // Assert that the result is Ok
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_edit_pin_and_delete_message() {
+        let bot = get_bot();
+
+        let message = bot
+            .send_message("test_edit_pin_and_delete_message", None)
+            .await
+            .expect("Failed to send message");
+
+        let edited = bot
+            .edit_message_text(
+                message.id,
+                "test_edit_pin_and_delete_message (edited)",
+                None,
+            )
+            .await
+            .expect("Failed to edit message");
+        assert_eq!(
+            edited.text.as_deref(),
+            Some("test_edit_pin_and_delete_message (edited)")
+        );
+
+        assert!(bot.pin_chat_message(message.id, true).await.is_ok());
+        assert!(bot.unpin_chat_message(Some(message.id)).await.is_ok());
+        assert!(bot.delete_message(message.id).await.is_ok());
+    }
 }
@@ -0,0 +1,191 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use tokio::time::Instant;
+
+/// Number of tracked chats above which chats without pending sends are
+/// forgotten.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Telegram's rate limits for sending messages.
+///
+/// See <https://core.telegram.org/bots/faq#my-bot-is-hitting-limits-how-do-i-avoid-this>
+///
+/// A limit of `0` disables it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// Allowed messages per second across all chats.
+    pub messages_per_sec_overall: u32,
+
+    /// Allowed messages per second in a single chat.
+    pub messages_per_sec_chat: u32,
+
+    /// Allowed messages per minute in a single group or channel.
+    pub messages_per_min_group: u32,
+}
+
+/// Client-side rate limiter which paces requests so that Telegram's [`Limits`]
+/// are never exceeded.
+///
+/// Calls to the same chat are let through in the order they were made, while
+/// calls to other chats are not held up by a busy chat.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limits: Limits,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    /// Earliest instant the next message may be sent to any chat.
+    next_overall: Instant,
+
+    /// Earliest instant the next message may be sent to a given chat.
+    next_chat: HashMap<String, Instant>,
+}
+
+impl Default for Limits {
+    /// 30 messages per second overall, 1 message per second per chat, and 20
+    /// messages per minute per group.
+    fn default() -> Self {
+        Self {
+            messages_per_sec_overall: 30,
+            messages_per_sec_chat: 1,
+            messages_per_min_group: 20,
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Creates a new `RateLimiter` enforcing `limits`.
+    #[must_use]
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            state: Mutex::new(State {
+                next_overall: Instant::now(),
+                next_chat: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Returns the enforced limits.
+    #[must_use]
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Waits until a message can be sent to `chat_id` without exceeding the
+    /// limits.
+    pub async fn acquire(&self, chat_id: &str) {
+        // The global slot is reserved only once the chat slot is reached, so
+        // that a busy chat does not hold the global queue up for other chats.
+        tokio::time::sleep_until(self.reserve_chat(chat_id, Instant::now())).await;
+        tokio::time::sleep_until(self.reserve_overall(Instant::now())).await;
+    }
+
+    /// Reserves the earliest slot at or after `now` for a message to
+    /// `chat_id` and returns its instant.
+    fn reserve_chat(&self, chat_id: &str, now: Instant) -> Instant {
+        let mut chat = interval(1, self.limits.messages_per_sec_chat);
+        if is_group(chat_id) {
+            chat = chat.max(interval(60, self.limits.messages_per_min_group));
+        }
+
+        let mut state = self.state.lock().expect("rate limiter state is poisoned");
+
+        if state.next_chat.len() > PRUNE_THRESHOLD {
+            state.next_chat.retain(|_, next| *next > now);
+        }
+
+        let at = match state.next_chat.get(chat_id) {
+            Some(next) => now.max(*next),
+            None => now,
+        };
+        state.next_chat.insert(chat_id.to_owned(), at + chat);
+
+        at
+    }
+
+    /// Reserves the earliest slot at or after `now` for a message to any chat
+    /// and returns its instant.
+    fn reserve_overall(&self, now: Instant) -> Instant {
+        let overall = interval(1, self.limits.messages_per_sec_overall);
+
+        let mut state = self.state.lock().expect("rate limiter state is poisoned");
+        let at = now.max(state.next_overall);
+        state.next_overall = at + overall;
+
+        at
+    }
+}
+
+/// Returns the minimal interval between two messages allowed by a limit of
+/// `count` messages per `secs` seconds.
+fn interval(secs: u64, count: u32) -> Duration {
+    match count {
+        0 => Duration::ZERO,
+        count => Duration::from_secs(secs) / count,
+    }
+}
+
+/// Returns whether `chat_id` refers to a group, supergroup or channel rather
+/// than to a private chat.
+fn is_group(chat_id: &str) -> bool {
+    chat_id.starts_with('-') || chat_id.starts_with('@')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use crate::throttle::{Limits, RateLimiter};
+
+    #[test]
+    fn reserve_chat_paces_private_chats_and_groups() {
+        let limiter = RateLimiter::new(Limits::default());
+        let now = Instant::now();
+
+        assert_eq!(limiter.reserve_chat("1", now), now);
+        assert_eq!(limiter.reserve_chat("1", now), now + Duration::from_secs(1));
+        assert_eq!(limiter.reserve_chat("2", now), now);
+
+        assert_eq!(limiter.reserve_chat("-100123", now), now);
+        assert_eq!(
+            limiter.reserve_chat("-100123", now),
+            now + Duration::from_secs(3)
+        );
+        assert_eq!(limiter.reserve_chat("@channel", now), now);
+    }
+
+    #[test]
+    fn reserve_overall_paces_all_messages() {
+        let limiter = RateLimiter::new(Limits::default());
+        let now = Instant::now();
+
+        assert_eq!(limiter.reserve_overall(now), now);
+        assert_eq!(
+            limiter.reserve_overall(now),
+            now + Duration::from_secs(1) / 30
+        );
+
+        let later = now + Duration::from_secs(1);
+        assert_eq!(limiter.reserve_overall(later), later);
+    }
+
+    #[test]
+    fn zero_limits_are_disabled() {
+        let limiter = RateLimiter::new(Limits {
+            messages_per_sec_overall: 0,
+            messages_per_sec_chat: 0,
+            messages_per_min_group: 0,
+        });
+        let now = Instant::now();
+
+        assert_eq!(limiter.reserve_chat("1", now), now);
+        assert_eq!(limiter.reserve_chat("1", now), now);
+        assert_eq!(limiter.reserve_overall(now), now);
+        assert_eq!(limiter.reserve_overall(now), now);
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::{bot::Bot, errors::Error};
+
+/// Topic ids by name, per chat.
+type Topics = BTreeMap<String, BTreeMap<String, i32>>;
+
+/// Finds forum topics by name, creating them on first use, e.g. one topic
+/// per strategy.
+///
+/// Telegram cannot list the topics of a chat, so the ids of the topics are
+/// remembered in memory and, if a file is configured, on disk, so that
+/// topics are not created twice after a restart.
+#[derive(Debug, Default)]
+pub struct TopicCache {
+    topics: Mutex<Topics>,
+    path: Option<PathBuf>,
+}
+
+impl TopicCache {
+    /// Creates a new, empty `TopicCache` kept in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `TopicCache` which keeps the topic ids in the JSON file
+    /// at `path`, loading the ids stored there.
+    pub fn with_file<P>(path: P) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let topics = read_topics(&path)?;
+
+        Ok(Self {
+            topics: Mutex::new(topics),
+            path: Some(path),
+        })
+    }
+
+    /// Returns the id of the topic `name` in the chat of `bot`, creating the
+    /// topic if it is not known yet.
+    pub async fn get_or_create(&self, bot: &Bot, name: &str) -> Result<i32, Error> {
+        // Held while creating, so that concurrent calls create a topic once.
+        let mut topics = self.lock().await;
+        if let Some(id) = topics.get(bot.chat_id()).and_then(|chat| chat.get(name)) {
+            return Ok(*id);
+        }
+
+        let topic = bot.create_forum_topic(name, None).await?;
+        topics
+            .entry(bot.chat_id().to_owned())
+            .or_default()
+            .insert(name.to_owned(), topic.message_thread_id);
+        self.persist(&topics)?;
+
+        Ok(topic.message_thread_id)
+    }
+
+    /// Returns the id of the topic `name` in the chat of `bot`, if known.
+    pub async fn get(&self, bot: &Bot, name: &str) -> Option<i32> {
+        self.lock()
+            .await
+            .get(bot.chat_id())
+            .and_then(|chat| chat.get(name))
+            .copied()
+    }
+
+    /// Remembers the id of an existing topic `name` in the chat of `bot`.
+    pub async fn insert(&self, bot: &Bot, name: &str, message_thread_id: i32) -> Result<(), Error> {
+        let mut topics = self.lock().await;
+        topics
+            .entry(bot.chat_id().to_owned())
+            .or_default()
+            .insert(name.to_owned(), message_thread_id);
+        self.persist(&topics)
+    }
+
+    /// Forgets the topic `name` in the chat of `bot`, e.g. after it has been
+    /// deleted, so that the next [`TopicCache::get_or_create`] creates it
+    /// again.
+    pub async fn forget(&self, bot: &Bot, name: &str) -> Result<(), Error> {
+        let mut topics = self.lock().await;
+        if let Some(chat) = topics.get_mut(bot.chat_id()) {
+            chat.remove(name);
+        }
+        self.persist(&topics)
+    }
+
+    async fn lock(&self) -> MutexGuard<'_, Topics> {
+        self.topics.lock().await
+    }
+
+    fn persist(&self, topics: &Topics) -> Result<(), Error> {
+        match &self.path {
+            Some(path) => write_topics(path, topics),
+            None => Ok(()),
+        }
+    }
+}
+
+fn read_topics(path: &Path) -> Result<Topics, Error> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(serde_json::from_str(&contents)?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Topics::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Writes `topics` to `path`, replacing the file atomically.
+fn write_topics(path: &Path, topics: &Topics) -> Result<(), Error> {
+    let mut tmp_path = path.to_path_buf();
+    tmp_path.set_extension("tmp");
+
+    fs::write(&tmp_path, serde_json::to_vec_pretty(topics)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::{bot::Bot, topics::TopicCache};
+
+    #[tokio::test]
+    async fn topics_survive_reopening() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("topics.json");
+        let desk = Bot::new("token", "-100");
+        let other = desk.for_chat(-200);
+
+        let cache = TopicCache::with_file(&path).unwrap();
+        cache.insert(&desk, "btc-basis", 11).await.unwrap();
+        cache.insert(&desk, "eth-momentum", 12).await.unwrap();
+        cache.forget(&desk, "eth-momentum").await.unwrap();
+        drop(cache);
+
+        let cache = TopicCache::with_file(&path).unwrap();
+        assert_eq!(cache.get(&desk, "btc-basis").await, Some(11));
+        assert_eq!(cache.get(&desk, "eth-momentum").await, None);
+        assert_eq!(cache.get(&other, "btc-basis").await, None);
+        assert_eq!(cache.get_or_create(&desk, "btc-basis").await.unwrap(), 11);
+    }
+}
@@ -1,4 +1,9 @@
+use std::fmt;
+
+use crate::long_text::LongText;
+
 /// Parse mode for `sendMessage` API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum SendMessageParseMode {
     /// MarkdownV2 style
     MarkdownV2,
@@ -8,40 +13,90 @@ pub enum SendMessageParseMode {
 }
 
 /// Options which can be used with `sendMessage` API
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct SendMessageOption {
     /// Parse mode
     pub parse_mode: Option<SendMessageParseMode>,
+
+    /// Inline keyboard, custom reply keyboard, instructions to remove a reply
+    /// keyboard or to force a reply from the user
+    pub reply_markup: Option<ReplyMarkup>,
+
+    /// Sends the message silently, users receive a notification with no sound
+    pub disable_notification: bool,
+
+    /// Forum topic to send the message to, in forum supergroups only
+    pub message_thread_id: Option<i32>,
+
+    /// What is done with text longer than Telegram accepts
+    pub long_text: LongText,
 }
 
-/// Status code indicating the result of APIs related function call.
-#[derive(Debug, Clone, Copy)]
-pub enum StatusCode {
-    /// Success
-    Success = 0,
+/// Contains information about why a request was unsuccessful.
+/// See <https://core.telegram.org/bots/api#responseparameters>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize)]
+pub struct ResponseParameters {
+    /// The group has been migrated to a supergroup with the specified
+    /// identifier.
+    pub migrate_to_chat_id: Option<i64>,
 
-    /// Internal error due to various internal operations.
-    /// Whenever Telegram's related operations occurred with error, then this
-    /// value will be used.
-    ErrorInternalError,
+    /// In case of exceeding flood control, the number of seconds left to wait
+    /// before the request can be repeated.
+    pub retry_after: Option<u32>,
 }
 
-impl StatusCode {
-    /// Get the status code as u16.
-    pub fn as_u16(&self) -> u16 {
-        *self as u16
-    }
+/// Identifier of a target chat: the numeric id of a chat or user, or the
+/// username of a public channel or supergroup.
+///
+/// Strings are converted as Telegram expects them: numeric strings become
+/// [`ChatId::Id`], anything else a username with a leading `@`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(untagged, from = "RawChatId")]
+pub enum ChatId {
+    /// Numeric id, e.g. `-1001234567890`.
+    Id(i64),
+
+    /// Username including the leading `@`, e.g. `@channelusername`.
+    Username(String),
+}
+
+/// A [`ChatId`] as found in configuration files, either a number or a
+/// string.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum RawChatId {
+    Id(i64),
+    Text(String),
 }
 
 /// Request Object for `sendMessage` API
 /// See <https://core.telegram.org/bots/api#sendmessage>
 /// NOTE: serde::Serialize can work with &str
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct RequestObj {
     chat_id: String,
     text: String,
     // this is required unfortunately, see https://github.com/serde-rs/serde/issues/947
     #[serde(skip_serializing_if = "Option::is_none")]
     parse_mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_markup: Option<ReplyMarkup>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    disable_notification: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message_thread_id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entities: Option<Vec<MessageEntity>>,
+}
+
+/// Text of a message, either formatted with the parse mode of the message
+/// options, or with explicit entities, e.g. built with [`RichText`].
+///
+/// [`RichText`]: crate::rich_text::RichText
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MessageText {
+    text: String,
+    entities: Option<Vec<MessageEntity>>,
 }
 
 impl RequestObj {
@@ -51,6 +106,856 @@ impl RequestObj {
             chat_id: chat_id.to_owned(),
             text: text.to_owned(),
             parse_mode,
+            reply_markup: None,
+            disable_notification: false,
+            message_thread_id: None,
+            entities: None,
+        }
+    }
+
+    /// Attaches a keyboard or other reply interface to the message.
+    #[must_use]
+    pub fn with_reply_markup(mut self, reply_markup: Option<ReplyMarkup>) -> Self {
+        self.reply_markup = reply_markup;
+        self
+    }
+
+    /// Sends the message silently, users receive a notification with no sound.
+    #[must_use]
+    pub fn with_disable_notification(mut self, disable_notification: bool) -> Self {
+        self.disable_notification = disable_notification;
+        self
+    }
+
+    /// Sends the message to a forum topic.
+    #[must_use]
+    pub fn with_message_thread_id(mut self, message_thread_id: Option<i32>) -> Self {
+        self.message_thread_id = message_thread_id;
+        self
+    }
+
+    /// Formats the message with `entities` instead of a parse mode.
+    #[must_use]
+    pub fn with_entities(mut self, entities: Option<Vec<MessageEntity>>) -> Self {
+        if entities.is_some() {
+            self.parse_mode = None;
+        }
+        self.entities = entities;
+        self
+    }
+
+    /// Returns the chat id the message is sent to.
+    #[must_use]
+    pub fn chat_id(&self) -> &str {
+        &self.chat_id
+    }
+}
+
+impl MessageText {
+    /// Creates a new `MessageText` formatted with the parse mode of the
+    /// message options, if any.
+    pub fn new<S>(text: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            text: text.into(),
+            entities: None,
+        }
+    }
+
+    /// Creates a new `MessageText` formatted with `entities`, ignoring the
+    /// parse mode of the message options.
+    pub fn with_entities<S>(text: S, entities: Vec<MessageEntity>) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            text: text.into(),
+            entities: Some(entities),
+        }
+    }
+
+    /// Returns the text without formatting entities.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the entities formatting the text, if set explicitly.
+    #[must_use]
+    pub fn entities(&self) -> Option<&[MessageEntity]> {
+        self.entities.as_deref()
+    }
+
+    /// Returns whether the text is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+}
+
+impl From<&str> for MessageText {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<String> for MessageText {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<&String> for MessageText {
+    fn from(text: &String) -> Self {
+        Self::new(text.as_str())
+    }
+}
+
+/// Additional interface options of a message: an inline keyboard, a custom
+/// reply keyboard, instructions to remove a reply keyboard or to force a reply
+/// from the user.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum ReplyMarkup {
+    InlineKeyboard(InlineKeyboardMarkup),
+    Keyboard(ReplyKeyboardMarkup),
+    KeyboardRemove(ReplyKeyboardRemove),
+    ForceReply(ForceReply),
+}
+
+/// An inline keyboard that appears right next to the message it belongs to.
+/// See <https://core.telegram.org/bots/api#inlinekeyboardmarkup>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, serde::Serialize, serde::Deserialize)]
+pub struct InlineKeyboardMarkup {
+    /// Rows of buttons.
+    pub inline_keyboard: Vec<Vec<InlineKeyboardButton>>,
+}
+
+/// A button of an inline keyboard. Exactly one of the optional fields must be
+/// used.
+/// See <https://core.telegram.org/bots/api#inlinekeyboardbutton>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct InlineKeyboardButton {
+    /// Label text on the button.
+    pub text: String,
+
+    /// HTTP or tg:// URL to be opened when the button is pressed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Data to be sent in a callback query to the bot when the button is
+    /// pressed, 1-64 bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_data: Option<String>,
+
+    /// Text to be copied to the clipboard when the button is pressed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copy_text: Option<CopyTextButton>,
+}
+
+/// An inline keyboard button that copies specified text to the clipboard.
+/// See <https://core.telegram.org/bots/api#copytextbutton>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct CopyTextButton {
+    /// The text to be copied to the clipboard, 1-256 characters.
+    pub text: String,
+}
+
+/// A custom keyboard with reply options.
+/// See <https://core.telegram.org/bots/api#replykeyboardmarkup>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, serde::Serialize, serde::Deserialize)]
+pub struct ReplyKeyboardMarkup {
+    /// Rows of buttons.
+    pub keyboard: Vec<Vec<KeyboardButton>>,
+
+    /// Always show the keyboard when the regular keyboard is hidden.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_persistent: Option<bool>,
+
+    /// Resize the keyboard vertically for optimal fit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resize_keyboard: Option<bool>,
+
+    /// Hide the keyboard as soon as it has been used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub one_time_keyboard: Option<bool>,
+
+    /// The placeholder to be shown in the input field when the keyboard is
+    /// active, 1-64 characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_field_placeholder: Option<String>,
+
+    /// Show the keyboard to specific users only: users mentioned in the text
+    /// and the sender of the message replied to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selective: Option<bool>,
+}
+
+/// A button of a custom reply keyboard. Its text is sent as a message when the
+/// button is pressed.
+/// See <https://core.telegram.org/bots/api#keyboardbutton>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct KeyboardButton {
+    /// Text of the button.
+    pub text: String,
+}
+
+/// Asks clients to remove the custom reply keyboard.
+/// See <https://core.telegram.org/bots/api#replykeyboardremove>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct ReplyKeyboardRemove {
+    /// Always `true`.
+    pub remove_keyboard: bool,
+
+    /// Remove the keyboard for specific users only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selective: Option<bool>,
+}
+
+/// Asks clients to display a reply interface to the user, as if the user had
+/// selected the bot's message and tapped "Reply".
+/// See <https://core.telegram.org/bots/api#forcereply>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct ForceReply {
+    /// Always `true`.
+    pub force_reply: bool,
+
+    /// The placeholder to be shown in the input field when the reply is
+    /// active, 1-64 characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_field_placeholder: Option<String>,
+
+    /// Force reply from specific users only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selective: Option<bool>,
+}
+
+/// Response Object of the Telegram Bot API for a successful request.
+/// See <https://core.telegram.org/bots/api#making-requests>
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct ResponseObj<T> {
+    pub ok: bool,
+    pub result: T,
+}
+
+/// Unique identifier of a message inside a chat.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(transparent)]
+pub struct MessageId(pub i32);
+
+/// A message.
+/// See <https://core.telegram.org/bots/api#message>
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct Message {
+    /// Unique message identifier inside this chat.
+    #[serde(rename = "message_id")]
+    pub id: MessageId,
+
+    /// Sender of the message, empty for messages sent to channels.
+    pub from: Option<User>,
+
+    /// Date the message was sent in Unix time.
+    pub date: i64,
+
+    /// Chat the message belongs to.
+    pub chat: Chat,
+
+    /// Text of the message.
+    pub text: Option<String>,
+
+    /// Special entities like usernames, URLs, bot commands, etc. that appear
+    /// in the text.
+    #[serde(default)]
+    pub entities: Vec<MessageEntity>,
+
+    /// Caption for the document.
+    pub caption: Option<String>,
+
+    /// Special entities like usernames, URLs, bot commands, etc. that appear
+    /// in the caption.
+    #[serde(default)]
+    pub caption_entities: Vec<MessageEntity>,
+
+    /// Information about the file, if the message is a general file.
+    pub document: Option<Document>,
+}
+
+/// An incoming update. At most one of the optional fields is present.
+/// See <https://core.telegram.org/bots/api#update>
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct Update {
+    /// The update's unique identifier. Identifiers are increasing, which
+    /// allows to ignore repeated updates.
+    #[serde(rename = "update_id")]
+    pub id: i64,
+
+    /// New incoming message of any kind.
+    pub message: Option<Message>,
+
+    /// New version of a message that is known to the bot and was edited.
+    pub edited_message: Option<Message>,
+
+    /// New incoming channel post of any kind.
+    pub channel_post: Option<Message>,
+
+    /// New version of a channel post that is known to the bot and was edited.
+    pub edited_channel_post: Option<Message>,
+
+    /// New incoming callback query, e.g. an inline keyboard button was
+    /// pressed.
+    pub callback_query: Option<CallbackQuery>,
+}
+
+/// An incoming callback query from a callback button of an inline keyboard.
+/// See <https://core.telegram.org/bots/api#callbackquery>
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct CallbackQuery {
+    /// Unique identifier for this query.
+    pub id: String,
+
+    /// Sender of the query.
+    pub from: User,
+
+    /// Message with the callback button that originated the query.
+    pub message: Option<Message>,
+
+    /// Identifier of the message sent via the bot in inline mode, that
+    /// originated the query.
+    pub inline_message_id: Option<String>,
+
+    /// Global identifier, uniquely corresponding to the chat to which the
+    /// message with the callback button was sent.
+    pub chat_instance: String,
+
+    /// Data associated with the callback button.
+    pub data: Option<String>,
+}
+
+/// Current status of a webhook.
+/// See <https://core.telegram.org/bots/api#webhookinfo>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Deserialize)]
+pub struct WebhookInfo {
+    /// Webhook URL, empty if webhook is not set up.
+    pub url: String,
+
+    /// `true`, if a custom certificate was provided for webhook certificate
+    /// checks.
+    pub has_custom_certificate: bool,
+
+    /// Number of updates awaiting delivery.
+    pub pending_update_count: u32,
+
+    /// Currently used webhook IP address.
+    pub ip_address: Option<String>,
+
+    /// Unix time for the most recent error that happened when trying to
+    /// deliver an update via webhook.
+    pub last_error_date: Option<i64>,
+
+    /// Error message in human-readable format for the most recent error that
+    /// happened when trying to deliver an update via webhook.
+    pub last_error_message: Option<String>,
+
+    /// The maximum allowed number of simultaneous HTTPS connections to the
+    /// webhook for update delivery.
+    pub max_connections: Option<u32>,
+
+    /// A list of update types the bot is subscribed to.
+    pub allowed_updates: Option<Vec<String>>,
+}
+
+/// A topic in a forum supergroup.
+/// See <https://core.telegram.org/bots/api#forumtopic>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Deserialize)]
+pub struct ForumTopic {
+    /// Unique identifier of the forum topic, used as `message_thread_id`.
+    pub message_thread_id: i32,
+
+    /// Name of the topic.
+    pub name: String,
+
+    /// Color of the topic icon in RGB format.
+    pub icon_color: u32,
+
+    /// Unique identifier of the custom emoji shown as the topic icon.
+    pub icon_custom_emoji_id: Option<String>,
+}
+
+/// A command of the bot, shown in the command menu of Telegram clients.
+/// See <https://core.telegram.org/bots/api#botcommand>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct BotCommand {
+    /// Text of the command without the leading `/`. 1-32 characters, can
+    /// contain only lowercase English letters, digits and underscores.
+    pub command: String,
+
+    /// Description of the command, 1-256 characters.
+    pub description: String,
+}
+
+impl BotCommand {
+    /// Creates a new `BotCommand`.
+    pub fn new<C, D>(command: C, description: D) -> Self
+    where
+        C: Into<String>,
+        D: Into<String>,
+    {
+        Self {
+            command: command.into(),
+            description: description.into(),
         }
     }
 }
+
+/// A chat.
+/// See <https://core.telegram.org/bots/api#chat>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Deserialize)]
+pub struct Chat {
+    /// Unique identifier for this chat.
+    pub id: i64,
+
+    /// Type of the chat.
+    #[serde(rename = "type")]
+    pub kind: ChatKind,
+
+    /// Title, for supergroups, channels and group chats.
+    pub title: Option<String>,
+
+    /// Username, for private chats, supergroups and channels if available.
+    pub username: Option<String>,
+
+    /// First name of the other party in a private chat.
+    pub first_name: Option<String>,
+
+    /// Last name of the other party in a private chat.
+    pub last_name: Option<String>,
+}
+
+/// Type of a [`Chat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatKind {
+    Private,
+    Group,
+    Supergroup,
+    Channel,
+}
+
+/// A Telegram user or bot.
+/// See <https://core.telegram.org/bots/api#user>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct User {
+    /// Unique identifier for this user or bot.
+    pub id: u64,
+
+    /// `true`, if this user is a bot.
+    pub is_bot: bool,
+
+    /// User's or bot's first name.
+    pub first_name: String,
+
+    /// User's or bot's last name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_name: Option<String>,
+
+    /// User's or bot's username.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// IETF language tag of the user's language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_code: Option<String>,
+}
+
+/// One special entity in a text message, e.g. a hashtag, a URL or bold text.
+/// See <https://core.telegram.org/bots/api#messageentity>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct MessageEntity {
+    /// Type of the entity, e.g. `bold`, `url` or `bot_command`.
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    /// Offset in UTF-16 code units to the start of the entity.
+    pub offset: usize,
+
+    /// Length of the entity in UTF-16 code units.
+    pub length: usize,
+
+    /// For `text_link` only, URL that will be opened after the user taps on
+    /// the text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// For `text_mention` only, the mentioned user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+
+    /// For `pre` only, the programming language of the entity text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// For `custom_emoji` only, unique identifier of the custom emoji.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_emoji_id: Option<String>,
+}
+
+/// A general file.
+/// See <https://core.telegram.org/bots/api#document>
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Deserialize)]
+pub struct Document {
+    /// Identifier for this file, which can be used to download or reuse the
+    /// file.
+    pub file_id: String,
+
+    /// Unique identifier for this file, which is supposed to be the same over
+    /// time and for different bots.
+    pub file_unique_id: String,
+
+    /// Original filename as defined by sender.
+    pub file_name: Option<String>,
+
+    /// MIME type of the file as defined by sender.
+    pub mime_type: Option<String>,
+
+    /// File size in bytes.
+    pub file_size: Option<u64>,
+}
+
+impl InlineKeyboardMarkup {
+    /// Creates a new `InlineKeyboardMarkup` with the given rows of buttons.
+    #[must_use]
+    pub fn new<R>(rows: R) -> Self
+    where
+        R: IntoIterator,
+        R::Item: IntoIterator<Item = InlineKeyboardButton>,
+    {
+        Self {
+            inline_keyboard: rows
+                .into_iter()
+                .map(|row| row.into_iter().collect())
+                .collect(),
+        }
+    }
+
+    /// Appends a row of buttons below the existing ones.
+    #[must_use]
+    pub fn row<R>(mut self, buttons: R) -> Self
+    where
+        R: IntoIterator<Item = InlineKeyboardButton>,
+    {
+        self.inline_keyboard.push(buttons.into_iter().collect());
+        self
+    }
+}
+
+impl InlineKeyboardButton {
+    /// Creates a button which opens `url` when pressed.
+    #[must_use]
+    pub fn url<T, U>(text: T, url: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        Self {
+            url: Some(url.into()),
+            ..Self::empty(text.into())
+        }
+    }
+
+    /// Creates a button which sends a callback query with `callback_data` to
+    /// the bot when pressed.
+    #[must_use]
+    pub fn callback<T, D>(text: T, callback_data: D) -> Self
+    where
+        T: Into<String>,
+        D: Into<String>,
+    {
+        Self {
+            callback_data: Some(callback_data.into()),
+            ..Self::empty(text.into())
+        }
+    }
+
+    /// Creates a button which copies `copy_text` to the clipboard when
+    /// pressed.
+    #[must_use]
+    pub fn copy_text<T, C>(text: T, copy_text: C) -> Self
+    where
+        T: Into<String>,
+        C: Into<String>,
+    {
+        Self {
+            copy_text: Some(CopyTextButton {
+                text: copy_text.into(),
+            }),
+            ..Self::empty(text.into())
+        }
+    }
+
+    fn empty(text: String) -> Self {
+        Self {
+            text,
+            url: None,
+            callback_data: None,
+            copy_text: None,
+        }
+    }
+}
+
+impl ReplyKeyboardMarkup {
+    /// Creates a new `ReplyKeyboardMarkup` with the given rows of buttons.
+    #[must_use]
+    pub fn new<R>(rows: R) -> Self
+    where
+        R: IntoIterator,
+        R::Item: IntoIterator<Item = KeyboardButton>,
+    {
+        Self {
+            keyboard: rows
+                .into_iter()
+                .map(|row| row.into_iter().collect())
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Appends a row of buttons below the existing ones.
+    #[must_use]
+    pub fn row<R>(mut self, buttons: R) -> Self
+    where
+        R: IntoIterator<Item = KeyboardButton>,
+    {
+        self.keyboard.push(buttons.into_iter().collect());
+        self
+    }
+}
+
+impl KeyboardButton {
+    /// Creates a new `KeyboardButton` with the given text.
+    #[must_use]
+    pub fn new<T>(text: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self { text: text.into() }
+    }
+}
+
+impl ReplyKeyboardRemove {
+    /// Creates a new `ReplyKeyboardRemove` for all users.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            remove_keyboard: true,
+            selective: None,
+        }
+    }
+}
+
+impl Default for ReplyKeyboardRemove {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ForceReply {
+    /// Creates a new `ForceReply` for all users.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            force_reply: true,
+            input_field_placeholder: None,
+            selective: None,
+        }
+    }
+}
+
+impl Default for ForceReply {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for ChatId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{id}"),
+            Self::Username(username) => f.write_str(username),
+        }
+    }
+}
+
+impl From<i64> for ChatId {
+    fn from(id: i64) -> Self {
+        Self::Id(id)
+    }
+}
+
+impl From<&str> for ChatId {
+    fn from(chat_id: &str) -> Self {
+        match (chat_id.parse(), chat_id.starts_with('@')) {
+            (Ok(id), _) => Self::Id(id),
+            (Err(_), true) => Self::Username(chat_id.to_owned()),
+            (Err(_), false) => Self::Username(format!("@{chat_id}")),
+        }
+    }
+}
+
+impl From<String> for ChatId {
+    fn from(chat_id: String) -> Self {
+        chat_id.as_str().into()
+    }
+}
+
+impl From<&String> for ChatId {
+    fn from(chat_id: &String) -> Self {
+        chat_id.as_str().into()
+    }
+}
+
+impl From<RawChatId> for ChatId {
+    fn from(raw: RawChatId) -> Self {
+        match raw {
+            RawChatId::Id(id) => Self::Id(id),
+            RawChatId::Text(text) => text.into(),
+        }
+    }
+}
+
+impl From<InlineKeyboardMarkup> for ReplyMarkup {
+    fn from(markup: InlineKeyboardMarkup) -> Self {
+        Self::InlineKeyboard(markup)
+    }
+}
+
+impl From<ReplyKeyboardMarkup> for ReplyMarkup {
+    fn from(markup: ReplyKeyboardMarkup) -> Self {
+        Self::Keyboard(markup)
+    }
+}
+
+impl From<ReplyKeyboardRemove> for ReplyMarkup {
+    fn from(markup: ReplyKeyboardRemove) -> Self {
+        Self::KeyboardRemove(markup)
+    }
+}
+
+impl From<ForceReply> for ReplyMarkup {
+    fn from(markup: ForceReply) -> Self {
+        Self::ForceReply(markup)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{
+        ChatId, ChatKind, ForceReply, InlineKeyboardButton, InlineKeyboardMarkup, Message,
+        MessageId, ReplyMarkup, RequestObj, ResponseObj, Update,
+    };
+
+    #[test]
+    fn chat_id_converts_and_serializes() {
+        assert_eq!(ChatId::from("-1001234"), ChatId::Id(-1001234));
+        assert_eq!(
+            ChatId::from("mychannel"),
+            ChatId::Username("@mychannel".to_owned())
+        );
+        assert_eq!(ChatId::from("@mychannel").to_string(), "@mychannel");
+
+        assert_eq!(serde_json::to_string(&ChatId::Id(42)).unwrap(), "42");
+        assert_eq!(
+            serde_json::from_str::<Vec<ChatId>>(r#"[42, "-7", "@ops"]"#).unwrap(),
+            [
+                ChatId::Id(42),
+                ChatId::Id(-7),
+                ChatId::Username("@ops".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn send_message_response_deserializes() {
+        let raw = r#"{
+            "ok": true,
+            "result": {
+                "message_id": 42,
+                "from": { "id": 1, "is_bot": true, "first_name": "rustygram", "username": "rustygram_bot" },
+                "chat": { "id": -1001, "title": "ops", "type": "supergroup" },
+                "date": 1700000000,
+                "text": "BTC filled",
+                "entities": [{ "offset": 0, "length": 3, "type": "bold" }]
+            }
+        }"#;
+
+        let response: ResponseObj<Message> = serde_json::from_str(raw).unwrap();
+        let message = response.result;
+
+        assert_eq!(message.id, MessageId(42));
+        assert_eq!(message.chat.kind, ChatKind::Supergroup);
+        assert_eq!(message.text.as_deref(), Some("BTC filled"));
+        assert_eq!(message.entities[0].kind, "bold");
+        assert!(message.document.is_none());
+    }
+
+    #[test]
+    fn inline_keyboard_serializes() {
+        let markup = InlineKeyboardMarkup::default()
+            .row([
+                InlineKeyboardButton::url("Open dashboard", "https://example.com"),
+                InlineKeyboardButton::callback("Acknowledge", "ack:42"),
+            ])
+            .row([InlineKeyboardButton::copy_text("Copy id", "42")]);
+        let request = RequestObj::new("-100", "alert", None).with_reply_markup(Some(markup.into()));
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "chat_id": "-100",
+                "text": "alert",
+                "reply_markup": {
+                    "inline_keyboard": [
+                        [
+                            { "text": "Open dashboard", "url": "https://example.com" },
+                            { "text": "Acknowledge", "callback_data": "ack:42" }
+                        ],
+                        [{ "text": "Copy id", "copy_text": { "text": "42" } }]
+                    ]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn reply_markup_round_trips() {
+        let markup = ReplyMarkup::from(ForceReply::new());
+        let json = serde_json::to_string(&markup).unwrap();
+
+        assert_eq!(json, r#"{"force_reply":true}"#);
+        assert_eq!(serde_json::from_str::<ReplyMarkup>(&json).unwrap(), markup);
+    }
+
+    #[test]
+    fn callback_query_update_deserializes() {
+        let raw = r#"{
+            "update_id": 10000,
+            "callback_query": {
+                "id": "4382bfdwdsb323b2d9",
+                "from": { "id": 1111111, "is_bot": false, "first_name": "Trader" },
+                "chat_instance": "-1234",
+                "data": "ack:42"
+            }
+        }"#;
+
+        let update: Update = serde_json::from_str(raw).unwrap();
+        let query = update.callback_query.unwrap();
+
+        assert_eq!(update.id, 10000);
+        assert!(update.message.is_none());
+        assert_eq!(query.from.first_name, "Trader");
+        assert_eq!(query.data.as_deref(), Some("ack:42"));
+    }
+}
@@ -1,8 +1,10 @@
-use crate::{
-    errors::ErrorResult,
-    types::{SendMessageParseMode, StatusCode},
+use std::{
+    fmt::{self, Write},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
+use crate::types::{SendMessageParseMode, User};
+
 /// Get a string representing of specified parse mode.
 ///
 /// This function returns static string as it is expected to be used across
@@ -18,15 +20,223 @@ pub fn get_send_message_parse_mode_str(mode: &SendMessageParseMode) -> &'static
     }
 }
 
-/// Create an `ErrorResult` from input of string slice.
+/// Characters which must be escaped in MarkdownV2 text.
+const MARKDOWN_V2_SPECIAL_CHARS: &str = "_*[]()~`>#+-=|{}.!\\";
+
+/// Formats a value for MarkdownV2 text, escaping all characters with a
+/// special meaning, so that dynamic content cannot break the formatting:
 ///
-/// # Arguments
+/// ```
+/// use rustygram::utils::Escaped;
+///
+/// let err = "order rejected (price-band 1.5%)!";
+/// assert_eq!(
+///     format!("*{}*", Escaped(err)),
+///     r"*order rejected \(price\-band 1\.5%\)\!*"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Escaped<T>(pub T);
+
+/// Formats a value for HTML text, escaping `<`, `>`, `&` and `"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EscapedHtml<T>(pub T);
+
+/// How the characters of a text are escaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Escaping {
+    MarkdownV2,
+    MarkdownV2Code,
+    MarkdownV2Url,
+    Html,
+}
+
+/// Escapes everything written to it into `out`.
+struct Escaper<W> {
+    out: W,
+    escaping: Escaping,
+}
+
+/// Escapes `text` for MarkdownV2 text, outside of code and link URLs.
+///
+/// See <https://core.telegram.org/bots/api#markdownv2-style>
+pub fn escape_markdown_v2(text: &str) -> String {
+    escape(text, Escaping::MarkdownV2)
+}
+
+/// Escapes `text` for the inside of a MarkdownV2 `code` or `pre` entity, where
+/// only `` ` `` and `\` must be escaped.
+pub fn escape_markdown_v2_code(text: &str) -> String {
+    escape(text, Escaping::MarkdownV2Code)
+}
+
+/// Escapes `text` for the URL of a MarkdownV2 inline link, i.e. the part
+/// within `(...)`, where only `)` and `\` must be escaped.
+pub fn escape_markdown_v2_url(text: &str) -> String {
+    escape(text, Escaping::MarkdownV2Url)
+}
+
+/// Escapes `text` for HTML text and attribute values.
 ///
-/// * `code` - `StatusCode`
-/// * `msg` - message string to add as an error description
-pub fn create_error_result_str(code: StatusCode, msg: &str) -> Result<(), ErrorResult> {
-    Err(ErrorResult {
-        code: code as u16,
-        msg: msg.to_string(),
-    })
+/// See <https://core.telegram.org/bots/api#html-style>
+pub fn escape_html(text: &str) -> String {
+    escape(text, Escaping::Html)
+}
+
+fn escape(text: &str, escaping: Escaping) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut escaper = Escaper {
+        out: &mut escaped,
+        escaping,
+    };
+    escaper
+        .write_str(text)
+        .expect("writing to a String cannot fail");
+
+    escaped
+}
+
+impl<T> fmt::Display for Escaped<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut escaper = Escaper {
+            out: f,
+            escaping: Escaping::MarkdownV2,
+        };
+        write!(escaper, "{}", self.0)
+    }
+}
+
+impl<T> fmt::Display for EscapedHtml<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut escaper = Escaper {
+            out: f,
+            escaping: Escaping::Html,
+        };
+        write!(escaper, "{}", self.0)
+    }
+}
+
+impl<W> Write for Escaper<W>
+where
+    W: Write,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        s.chars().try_for_each(|c| self.write_char(c))
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        let escape = match self.escaping {
+            Escaping::MarkdownV2 => MARKDOWN_V2_SPECIAL_CHARS.contains(c),
+            Escaping::MarkdownV2Code => matches!(c, '`' | '\\'),
+            Escaping::MarkdownV2Url => matches!(c, ')' | '\\'),
+            Escaping::Html => {
+                return match c {
+                    '<' => self.out.write_str("&lt;"),
+                    '>' => self.out.write_str("&gt;"),
+                    '&' => self.out.write_str("&amp;"),
+                    '"' => self.out.write_str("&quot;"),
+                    _ => self.out.write_char(c),
+                }
+            }
+        };
+
+        if escape {
+            self.out.write_char('\\')?;
+        }
+        self.out.write_char(c)
+    }
+}
+
+/// Returns `@username`, or the full name of users without username.
+pub fn display_name(user: &User) -> String {
+    match (&user.username, &user.last_name) {
+        (Some(username), _) => format!("@{username}"),
+        (None, Some(last_name)) => format!("{} {last_name}", user.first_name),
+        (None, None) => user.first_name.clone(),
+    }
+}
+
+/// Formats `time` as `YYYY-MM-DD HH:MM UTC`, e.g. to tell in a message when
+/// something happened.
+pub fn format_utc(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60
+    )
+}
+
+/// Converts days since the Unix epoch into a proleptic Gregorian date.
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::utils::{
+        escape_html, escape_markdown_v2, escape_markdown_v2_code, escape_markdown_v2_url,
+        format_utc, Escaped, EscapedHtml,
+    };
+
+    #[test]
+    fn markdown_v2_is_escaped() {
+        assert_eq!(
+            escape_markdown_v2("BTC-PERP +2.5% (1h)! [x_y] *a* ~b~ `c` >d #e =f |g| {h} \\"),
+            r"BTC\-PERP \+2\.5% \(1h\)\! \[x\_y\] \*a\* \~b\~ \`c\` \>d \#e \=f \|g\| \{h\} \\"
+        );
+        assert_eq!(
+            escape_markdown_v2_code(r"let s = `a\b` (1.0);"),
+            r"let s = \`a\\b\` (1.0);"
+        );
+        assert_eq!(
+            escape_markdown_v2_url("https://example.com/a_(b)?c=1.2"),
+            r"https://example.com/a_(b\)?c=1.2"
+        );
+        assert_eq!(format!("*{}*", Escaped(-1.5)), r"*\-1\.5*");
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        assert_eq!(
+            escape_html(r#"<b>P&L</b> "daily""#),
+            "&lt;b&gt;P&amp;L&lt;/b&gt; &quot;daily&quot;"
+        );
+        assert_eq!(format!("<b>{}</b>", EscapedHtml("a<b")), "<b>a&lt;b</b>");
+    }
+
+    #[test]
+    fn format_utc_formats_dates() {
+        assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01 00:00 UTC");
+        assert_eq!(
+            format_utc(UNIX_EPOCH + Duration::from_secs(1_709_210_096)),
+            "2024-02-29 12:34 UTC"
+        );
+    }
 }
@@ -0,0 +1,201 @@
+//! Webhook server receiving incoming updates, available with the `webhook`
+//! feature.
+//!
+//! Register the public URL of the server with [`Bot::set_webhook`], passing
+//! the same secret token as to [`serve`] or [`router`].
+//!
+//! [`Bot::set_webhook`]: crate::bot::Bot::set_webhook
+
+use std::{future::Future, io, net::SocketAddr, sync::Arc};
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+
+use crate::{errors::Error, types::Update};
+
+/// Header in which Telegram sends the secret token set with
+/// [`Bot::set_webhook`].
+///
+/// [`Bot::set_webhook`]: crate::bot::Bot::set_webhook
+pub const SECRET_TOKEN_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+
+struct Webhook<F> {
+    secret_token: Option<String>,
+    handler: F,
+}
+
+/// Runs an HTTP server on `addr` which receives updates posted by Telegram
+/// to `path`, and passes them to `handler`.
+///
+/// See [`router`] for how requests are handled. Returns only if the server
+/// fails.
+pub async fn serve<F, Fut>(
+    addr: SocketAddr,
+    path: &str,
+    secret_token: Option<String>,
+    handler: F,
+) -> Result<(), Error>
+where
+    F: Fn(Update) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let app = router(path, secret_token, handler);
+
+    axum::Server::try_bind(&addr)
+        .map_err(server_error)?
+        .serve(app.into_make_service())
+        .await
+        .map_err(server_error)
+}
+
+/// Creates a [`Router`] which receives updates posted by Telegram to `path`,
+/// so that it can be mounted into an existing server.
+///
+/// Requests without the matching `X-Telegram-Bot-Api-Secret-Token` header
+/// are rejected with `401 Unauthorized` when a `secret_token` is set. Each
+/// update is passed to `handler`, and Telegram is answered once the handler
+/// completes, so that an update is redelivered if the process dies while
+/// handling it.
+///
+/// Updates which cannot be decoded, e.g. of a kind this crate does not know,
+/// are logged and acknowledged without calling `handler`, as Telegram would
+/// otherwise redeliver them and hold back the updates after them.
+pub fn router<F, Fut>(path: &str, secret_token: Option<String>, handler: F) -> Router
+where
+    F: Fn(Update) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let webhook = Arc::new(Webhook {
+        secret_token,
+        handler,
+    });
+
+    Router::new()
+        .route(path, post(receive::<F, Fut>))
+        .with_state(webhook)
+}
+
+async fn receive<F, Fut>(
+    State(webhook): State<Arc<Webhook<F>>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode
+where
+    F: Fn(Update) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    if let Some(expected) = &webhook.secret_token {
+        let received = headers
+            .get(SECRET_TOKEN_HEADER)
+            .map(|value| value.as_bytes())
+            .unwrap_or_default();
+
+        if !constant_time_eq(received, expected.as_bytes()) {
+            return StatusCode::UNAUTHORIZED;
+        }
+    }
+
+    let update = match serde_json::from_slice::<Update>(&body) {
+        Ok(update) => update,
+        Err(err) => {
+            log::warn!("skipped an undecodable webhook update: {err}");
+            return StatusCode::OK;
+        }
+    };
+
+    (webhook.handler)(update).await;
+    StatusCode::OK
+}
+
+/// Compares two byte strings in time independent of where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn server_error<E>(err: E) -> Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    Error::Io(io::Error::other(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    use crate::webhook::{router, SECRET_TOKEN_HEADER};
+
+    const UPDATE: &str = r#"{
+        "update_id": 1,
+        "message": {
+            "message_id": 2,
+            "date": 1700000000,
+            "chat": { "id": 3, "type": "private", "first_name": "Trader" },
+            "text": "/status"
+        }
+    }"#;
+
+    fn request(secret_token: Option<&str>) -> Request<Body> {
+        let mut request = Request::post("/telegram").header("content-type", "application/json");
+        if let Some(secret_token) = secret_token {
+            request = request.header(SECRET_TOKEN_HEADER, secret_token);
+        }
+        request.body(Body::from(UPDATE)).unwrap()
+    }
+
+    fn received_updates() -> (Router, Arc<Mutex<Vec<i64>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let app = {
+            let received = Arc::clone(&received);
+            router("/telegram", Some("s3cr3t".to_owned()), move |update| {
+                let received = Arc::clone(&received);
+                async move { received.lock().unwrap().push(update.id) }
+            })
+        };
+        (app, received)
+    }
+
+    #[tokio::test]
+    async fn webhook_verifies_secret_token() {
+        let (app, received) = received_updates();
+
+        let response = app.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.clone().oneshot(request(Some("wrong"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.oneshot(request(Some("s3cr3t"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(*received.lock().unwrap(), [1]);
+    }
+
+    #[tokio::test]
+    async fn undecodable_update_is_acknowledged() {
+        let (app, received) = received_updates();
+
+        let request = Request::post("/telegram")
+            .header("content-type", "application/json")
+            .header(SECRET_TOKEN_HEADER, "s3cr3t")
+            .body(Body::from(
+                r#"{ "update_id": 2, "message": { "message_id": "unknown" } }"#,
+            ))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(received.lock().unwrap().is_empty());
+    }
+}